@@ -0,0 +1,202 @@
+use bevy::{
+    prelude::*,
+    utils::HashMap,
+};
+
+use crate::settings::*;
+use crate::soft_body::*;
+
+// Contacts between bodies get collected for a whole substep first and are then
+// solved together, a few Gauss-Seidel passes over all of them. The normal impulses
+// each contact ends up with are kept for the next substep and applied up front
+// (warm starting), so bodies resting on each other dont have to build up the
+// impulse that holds them every time. Friction works along the contact, and can
+// only take as much as the normal impulse times the friction of the materials.
+
+// a contact point is a blend of two nodes, (index, weight) with the weights adding up to 1
+pub type ContactPoint = [(usize, f32); 2];
+
+// the same contact in the next substep has the same bodies and nodes
+type ContactKey = (Entity, Entity, [usize; 4]);
+
+#[derive(Clone, Debug)]
+pub struct SBContact{
+    pub bodies: [Entity; 2],
+    pub points: [ContactPoint; 2],
+    // the way the first point has to go to get out
+    pub normal: Vec2,
+    // the first point is out once (p1 - p2) . normal reaches this
+    pub target: f32,
+    // how fast they should move apart after, for the bounce
+    pub separating_vel: f32,
+    pub inverse_mass: f32,
+    pub friction: f32,
+    // normal impulse applied so far
+    pub impulse: f32,
+    // and along the contact, for the friction
+    pub tangent_impulse: f32,
+}
+
+impl SBContact{
+    // `correction` is how far the first point has to move to get out
+    pub fn new(
+        bodies: [Entity; 2],
+        sbs: [&SB; 2],
+        points: [ContactPoint; 2],
+        correction: Vec2,
+    ) -> Option<Self>{
+        let depth = correction.length();
+
+        if depth <= f32::EPSILON{
+            return None;
+        }
+
+        let normal = correction / depth;
+
+        // how hard the contact is to move
+        let inverse_mass = contact_inverse_mass(sbs[0], &points[0]) + contact_inverse_mass(sbs[1], &points[1]);
+
+        let gap = (contact_pos(sbs[0], &points[0]) - contact_pos(sbs[1], &points[1])).dot(normal);
+        let approach = (contact_vel(sbs[0], &points[0]) - contact_vel(sbs[1], &points[1])).dot(normal);
+
+        // the bouncier one wins, slippery against grippy ends up in between
+        let restitution = sbs[0].material.restitution.max(sbs[1].material.restitution);
+        let friction = (sbs[0].material.friction * sbs[1].material.friction).sqrt();
+
+        return Some(Self{
+            bodies,
+            points,
+            normal,
+            target: gap + depth,
+            separating_vel: if approach < 0.0 {-approach * restitution} else {0.0},
+            inverse_mass,
+            friction,
+            impulse: 0.0,
+            tangent_impulse: 0.0,
+        });
+    }
+
+    fn key(&self) -> ContactKey{
+        let [[(a1, _), (a2, _)], [(b1, _), (b2, _)]] = self.points;
+
+        return (self.bodies[0], self.bodies[1], [a1, a2, b1, b2]);
+    }
+}
+
+// the contacts of the last substep, kept around to warm start the next one
+#[derive(Resource, Default)]
+pub struct SBContacts{
+    pub contacts: Vec<SBContact>,
+    impulses: HashMap<ContactKey, f32>,
+}
+
+pub fn contact_pos(
+    sb: &SB,
+    point: &ContactPoint,
+) -> Vec2{
+    return point.iter().map(|(index, weight)| sb.nodes[*index].write_pos * *weight).sum();
+}
+
+pub fn contact_vel(
+    sb: &SB,
+    point: &ContactPoint,
+) -> Vec2{
+    return point.iter().map(|(index, weight)| sb.nodes[*index].vel * *weight).sum();
+}
+
+// every node counts by how much of it is in the contact point
+pub fn contact_inverse_mass(
+    sb: &SB,
+    point: &ContactPoint,
+) -> f32{
+    return point.iter().map(|(index, weight)| weight * weight * sb.nodes[*index].inverse_mass()).sum();
+}
+
+// moves the point by `pos_delta` and speeds it up by `vel_delta`, shared out by inverse mass
+pub fn apply_contact_point(
+    sb: &mut SB,
+    point: &ContactPoint,
+    pos_delta: Vec2,
+    vel_delta: Vec2,
+){
+    for (index, weight) in point{
+        let node = &mut sb.nodes[*index];
+        let inverse_mass = node.inverse_mass();
+
+        node.write_pos += pos_delta * *weight * inverse_mass;
+        node.vel += vel_delta * *weight * inverse_mass;
+    }
+}
+
+// moves the first point by `delta` and the second one back by it
+fn apply_contact(
+    sb1: &mut SB,
+    sb2: &mut SB,
+    points: &[ContactPoint; 2],
+    pos_delta: Vec2,
+    vel_delta: Vec2,
+){
+    apply_contact_point(sb1, &points[0], pos_delta, vel_delta);
+    apply_contact_point(sb2, &points[1], -pos_delta, -vel_delta);
+}
+
+pub fn solve_contacts(
+    sb_query: &mut Query<(Entity, &mut SB)>,
+    contacts: &mut SBContacts,
+){
+    let SBContacts{contacts, impulses} = contacts;
+
+    for contact in contacts.iter_mut(){
+        let Some(impulse) = impulses.get(&contact.key()) else{
+            continue;
+        };
+
+        let Ok([(_, mut sb1), (_, mut sb2)]) = sb_query.get_many_mut(contact.bodies) else{
+            continue;
+        };
+
+        contact.impulse = impulse * WARM_START_FACTOR;
+
+        apply_contact(&mut sb1, &mut sb2, &contact.points, Vec2::ZERO, contact.normal * contact.impulse);
+    }
+
+    for _ in 0..CONTACT_ITERATIONS{
+        for contact in contacts.iter_mut(){
+            let Ok([(_, mut sb1), (_, mut sb2)]) = sb_query.get_many_mut(contact.bodies) else{
+                continue;
+            };
+
+            let approach = (contact_vel(&sb1, &contact.points[0]) - contact_vel(&sb2, &contact.points[1])).dot(contact.normal);
+
+            // the total impulse can only ever push, so it gets clamped instead of each step
+            let impulse = (contact.impulse + (contact.separating_vel - approach) / contact.inverse_mass).max(0.0);
+            let impulse_delta = impulse - contact.impulse;
+            contact.impulse = impulse;
+
+            let gap = (contact_pos(&sb1, &contact.points[0]) - contact_pos(&sb2, &contact.points[1])).dot(contact.normal);
+            let push = (contact.target - gap).max(0.0) / contact.inverse_mass;
+
+            apply_contact(&mut sb1, &mut sb2, &contact.points, contact.normal * push, contact.normal * impulse_delta);
+
+            if contact.friction <= 0.0{
+                continue;
+            }
+
+            let tangent = contact.normal.perp();
+            let slide = (contact_vel(&sb1, &contact.points[0]) - contact_vel(&sb2, &contact.points[1])).dot(tangent);
+
+            let limit = contact.friction * contact.impulse;
+            let tangent_impulse = (contact.tangent_impulse - slide / contact.inverse_mass).clamp(-limit, limit);
+            let tangent_delta = tangent_impulse - contact.tangent_impulse;
+            contact.tangent_impulse = tangent_impulse;
+
+            apply_contact(&mut sb1, &mut sb2, &contact.points, Vec2::ZERO, tangent * tangent_delta);
+        }
+    }
+
+    impulses.clear();
+
+    for contact in contacts.iter(){
+        impulses.insert(contact.key(), contact.impulse);
+    }
+}
@@ -0,0 +1,506 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::soft_body::*;
+use crate::shapes::*;
+use crate::meshing::*;
+use crate::svg::*;
+use crate::sprite::*;
+
+// keys 1 to 0 pick the shape that space spawns and tab goes to the next one.
+// The files come first, then the drawings and the generated shapes from shapes.rs
+pub const SHAPE_FILES: [&str; 7] = [
+    "shapes/cube.sb.ron",
+    "shapes/triangle.sb.ron",
+    "shapes/rectangle.sb.ron",
+    "shapes/tetris1.sb.ron",
+    "shapes/tetris2.sb.ron",
+    "shapes/star.sb.ron",
+    "shapes/apple.sb.png",
+];
+
+// every closed outline in a drawing spawns as its own body
+pub const DRAWING_FILES: [&str; 1] = [
+    "shapes/blobs.svg",
+];
+
+pub struct SBDefinitionPlugin;
+
+impl Plugin for SBDefinitionPlugin{
+    fn build(&self, app: &mut App){
+        app.init_asset::<SBDefinition>()
+        .init_asset_loader::<SBDefinitionLoader>()
+        .add_systems(Startup, load_shapes)
+        .add_systems(Update, (select_shape, reload_shapes, reload_drawings).in_set(SBSet::Input));
+    }
+}
+
+// what a *.sb.ron file holds. Instead of nodes and connections a file can
+// give just an outline, which gets meshed when it loads
+#[derive(Asset, TypePath, Clone, Debug, Serialize, Deserialize)]
+pub struct SBDefinition{
+    #[serde(default)]
+    pub material: SBMaterial,
+    #[serde(default)]
+    pub nodes: Vec<Vec2>,
+    #[serde(default)]
+    pub connections: Vec<SBConnectionDefinition>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outline: Option<Vec<Vec2>>,
+    // distance between the extra nodes put inside the outline, none when left out
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub interior_spacing: Option<f32>,
+    // image drawn over the body, bodies made from a png have one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub texture: Option<SBTexture>,
+    // ropes and cloth strips, the edges dont have to close into an outline
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub open: bool,
+}
+
+// anything left out falls back to the node positions or the material
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SBConnectionDefinition{
+    pub i1: usize,
+    pub i2: usize,
+    #[serde(default)]
+    pub is_edge: bool,
+    #[serde(default)]
+    pub resting_length: Option<f32>,
+    #[serde(default)]
+    pub stiffness: Option<f32>,
+    #[serde(default)]
+    pub dampening: Option<f32>,
+}
+
+impl SBDefinition{
+    // for shapes made in code, like the ones from shapes.rs
+    pub fn from_parts(nodes: &Vec<SBNode>, connections: &Vec<SBConnection>) -> Self{
+        let connections = connections.iter().map(|connection| SBConnectionDefinition{
+            i1: connection.i1,
+            i2: connection.i2,
+            is_edge: connection.is_edge,
+            resting_length: Some(connection.resting_length),
+            stiffness: Some(connection.stiffness),
+            dampening: Some(connection.dampening),
+        }).collect();
+
+        return Self{
+            material: SBMaterial::default(),
+            nodes: nodes.iter().map(|node| node.read_pos).collect(),
+            connections,
+            outline: None,
+            interior_spacing: None,
+            texture: None,
+            open: false,
+        };
+    }
+
+    // for meshed shapes, the connections take their stiffness from the material
+    // like they do for hand written ones
+    pub fn from_mesh(nodes: &Vec<SBNode>, connections: &Vec<SBConnection>, material: SBMaterial) -> Self{
+        let mut definition = SBDefinition::from_parts(nodes, connections);
+        definition.set_material(material);
+
+        return definition;
+    }
+
+    pub fn from_outline(outline: Vec<Vec2>, interior_spacing: Option<f32>) -> Result<Self, SBError>{
+        let mut definition = Self{
+            material: SBMaterial::default(),
+            nodes: Vec::new(),
+            connections: Vec::new(),
+            outline: Some(outline),
+            interior_spacing,
+            texture: None,
+            open: false,
+        };
+
+        definition.mesh_outline()?;
+
+        return Ok(definition);
+    }
+
+    // like from_mesh, the connections drop their own stiffness for the material's
+    pub fn set_material(&mut self, material: SBMaterial){
+        for connection in &mut self.connections{
+            connection.stiffness = None;
+            connection.dampening = None;
+        }
+
+        self.material = material;
+    }
+
+    // meshes the outline into nodes and connections, if there is one
+    pub fn mesh_outline(&mut self) -> Result<(), SBError>{
+        self.check_spacing()?;
+
+        let Some(outline) = self.outline.take() else{
+            return Ok(());
+        };
+
+        let (nodes, connections) = mesh_outline(&outline, self.interior_spacing);
+
+        let texture = self.texture.take();
+
+        *self = SBDefinition::from_mesh(&nodes, &connections, self.material.clone());
+        self.texture = texture;
+
+        return Ok(());
+    }
+
+    // the interior gets filled row by row, a spacing of 0 or less never gets to the end
+    fn check_spacing(&self) -> Result<(), SBError>{
+        if let Some(spacing) = self.interior_spacing{
+            if !(spacing > 0.0 && spacing.is_finite()){
+                return Err(SBError::InvalidSpacing{spacing});
+            }
+        }
+
+        return Ok(());
+    }
+
+    // nodes and connections as the body gets them, resting lengths measured where they are left out
+    fn parts(&self) -> (Vec<SBNode>, Vec<SBConnection>){
+        let nodes: Vec<SBNode> = self.nodes.iter().map(|pos| {
+            let mut node = SBNode::new(*pos);
+            node.mass = self.material.node_mass();
+
+            node
+        }).collect();
+
+        let connections: Vec<SBConnection> = self.connections.iter().map(|connection| {
+            // out of range indices measure as 0 here, validation reports them
+            let resting_length = connection.resting_length.unwrap_or_else(|| match (self.nodes.get(connection.i1), self.nodes.get(connection.i2)){
+                (Some(p1), Some(p2)) => p1.distance(*p2),
+                _ => 0.0,
+            });
+
+            let mut sb_connection = SBConnection::new(connection.i1, connection.i2, connection.is_edge, resting_length);
+            sb_connection.stiffness = connection.stiffness.unwrap_or(self.material.stiffness);
+            sb_connection.dampening = connection.dampening.unwrap_or(self.material.dampening);
+
+            sb_connection
+        }).collect();
+
+        return (nodes, connections);
+    }
+
+    pub fn validate(&self) -> Result<(), SBError>{
+        self.check_spacing()?;
+
+        let (nodes, connections) = self.parts();
+
+        return SB::validate(&nodes, &connections, self.open);
+    }
+
+    pub fn to_sb(&self) -> Result<SB, SBError>{
+        let (nodes, connections) = self.parts();
+
+        let mut sb = SB::try_new(&nodes, &connections, self.open)?;
+        sb.material = self.material.clone();
+
+        return Ok(sb);
+    }
+
+    // builds the body where `old` is, turned the same way and moving with it
+    pub fn to_sb_at(&self, old: &SB) -> Result<SB, SBError>{
+        let mut sb = self.to_sb()?;
+
+        let mut velocity = Vec2::ZERO;
+
+        for node in &old.nodes{
+            velocity += node.vel;
+        }
+
+        velocity /= old.nodes.len().max(1) as f32;
+
+        let center = sb.center;
+
+        for node in &mut sb.nodes{
+            let pos = ((node.read_pos - center) * old.scale).rotate(Vec2::from_angle(old.angle)) + old.center;
+
+            node.read_pos = pos;
+            node.write_pos = pos;
+            node.vel = velocity;
+        }
+
+        // the rest shape is the file's, at the size the old one was inflated to. The angle
+        // carries over, and so do the preset and the charge, which the file knows nothing about
+        for connection in &mut sb.connections{
+            connection.resting_length *= old.scale;
+        }
+
+        let base_skeleton: Vec<Vec2> = sb.base_skeleton.iter().map(|vec| *vec * old.scale).collect();
+
+        let mut moved_sb = SB::from_parts(&sb.nodes, &sb.connections, &base_skeleton, old.angle);
+        moved_sb.material = self.material.clone();
+        moved_sb.scale = old.scale;
+        moved_sb.target_scale = old.target_scale;
+
+        if let Some(preset) = old.preset{
+            moved_sb.set_preset(preset);
+        }
+
+        moved_sb.material.charge = old.material.charge;
+
+        return Ok(moved_sb);
+    }
+}
+
+// the shape file a body was spawned from, so it can be replaced when the file changes
+#[derive(Component, Clone, Debug)]
+pub struct SBSource(pub String);
+
+#[derive(Default)]
+pub struct SBDefinitionLoader;
+
+#[derive(Debug)]
+pub enum SBDefinitionLoaderError{
+    Io(std::io::Error),
+    Ron(ron::error::SpannedError),
+    Invalid(SBError),
+}
+
+impl fmt::Display for SBDefinitionLoaderError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        match self{
+            SBDefinitionLoaderError::Io(err) => write!(f, "could not read soft body definition: {}", err),
+            SBDefinitionLoaderError::Ron(err) => write!(f, "could not parse soft body definition: {}", err),
+            SBDefinitionLoaderError::Invalid(err) => write!(f, "invalid soft body definition: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SBDefinitionLoaderError{}
+
+impl From<std::io::Error> for SBDefinitionLoaderError{
+    fn from(err: std::io::Error) -> Self{
+        SBDefinitionLoaderError::Io(err)
+    }
+}
+
+impl From<ron::error::SpannedError> for SBDefinitionLoaderError{
+    fn from(err: ron::error::SpannedError) -> Self{
+        SBDefinitionLoaderError::Ron(err)
+    }
+}
+
+impl From<SBError> for SBDefinitionLoaderError{
+    fn from(err: SBError) -> Self{
+        SBDefinitionLoaderError::Invalid(err)
+    }
+}
+
+impl AssetLoader for SBDefinitionLoader{
+    type Asset = SBDefinition;
+    type Settings = ();
+    type Error = SBDefinitionLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<SBDefinition, SBDefinitionLoaderError>{
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        let mut definition = ron::de::from_bytes::<SBDefinition>(&bytes)?;
+        definition.mesh_outline()?;
+        definition.validate()?;
+
+        return Ok(definition);
+    }
+
+    fn extensions(&self) -> &[&str]{
+        return &["sb.ron"];
+    }
+}
+
+#[derive(Clone, Debug)]
+pub enum SBShape{
+    Definition(Handle<SBDefinition>),
+    Drawing(Handle<SBDrawing>),
+}
+
+#[derive(Resource)]
+pub struct SBShapes{
+    pub shapes: Vec<(String, SBShape)>,
+    pub selected: usize,
+}
+
+// the source of the `index`th body of a drawing
+pub fn drawing_source(name: &str, index: usize) -> String{
+    return format!("{}#{}", name, index);
+}
+
+fn load_shapes(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut definitions: ResMut<Assets<SBDefinition>>,
+){
+    let mut shapes: Vec<(String, SBShape)> = SHAPE_FILES.iter().map(|path| (path.to_string(), SBShape::Definition(asset_server.load(*path)))).collect();
+
+    for path in DRAWING_FILES{
+        shapes.push((path.to_string(), SBShape::Drawing(asset_server.load(path))));
+    }
+
+    for (name, definition) in default_generated_shapes(){
+        shapes.push((name, SBShape::Definition(definitions.add(definition))));
+    }
+
+    // tetris2, what space used to spawn
+    commands.insert_resource(SBShapes{shapes, selected: 4});
+}
+
+fn reload_shapes(
+    mut asset_events: EventReader<AssetEvent<SBDefinition>>,
+    shapes: Res<SBShapes>,
+    definitions: Res<Assets<SBDefinition>>,
+    mut input_writer: EventWriter<SBInput>,
+){
+    for event in asset_events.read(){
+        let AssetEvent::Modified{id} = event else{
+            continue;
+        };
+
+        for (name, shape) in &shapes.shapes{
+            let SBShape::Definition(handle) = shape else{
+                continue;
+            };
+
+            if handle.id() != *id{
+                continue;
+            }
+
+            if let Some(definition) = definitions.get(handle){
+                input_writer.send(SBInput::Reload{definition: Box::new(definition.clone()), source: name.clone()});
+            }
+        }
+    }
+}
+
+fn reload_drawings(
+    mut asset_events: EventReader<AssetEvent<SBDrawing>>,
+    shapes: Res<SBShapes>,
+    drawings: Res<Assets<SBDrawing>>,
+    mut input_writer: EventWriter<SBInput>,
+){
+    for event in asset_events.read(){
+        let AssetEvent::Modified{id} = event else{
+            continue;
+        };
+
+        for (name, shape) in &shapes.shapes{
+            let SBShape::Drawing(handle) = shape else{
+                continue;
+            };
+
+            if handle.id() != *id{
+                continue;
+            }
+
+            let Some(drawing) = drawings.get(handle) else{
+                continue;
+            };
+
+            // bodies are matched up by their order in the file
+            for (index, definition) in drawing.bodies.iter().enumerate(){
+                input_writer.send(SBInput::Reload{definition: Box::new(definition.clone()), source: drawing_source(name, index)});
+            }
+        }
+    }
+}
+
+fn select_shape(
+    input: Res<ButtonInput<KeyCode>>,
+    mut shapes: ResMut<SBShapes>,
+){
+    let keys = [
+        KeyCode::Digit1, KeyCode::Digit2, KeyCode::Digit3,
+        KeyCode::Digit4, KeyCode::Digit5, KeyCode::Digit6,
+        KeyCode::Digit7, KeyCode::Digit8, KeyCode::Digit9,
+        KeyCode::Digit0,
+    ];
+
+    for (index, key) in keys.iter().enumerate(){
+        if index < shapes.shapes.len() && input.just_pressed(*key){
+            shapes.selected = index;
+            info!("Selected {}", shapes.shapes[index].0);
+        }
+    }
+
+    if input.just_pressed(KeyCode::Tab){
+        shapes.selected = (shapes.selected + 1) % shapes.shapes.len();
+        info!("Selected {}", shapes.shapes[shapes.selected].0);
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::material::*;
+
+    // clockwise, like soft body outlines
+    fn square() -> Vec<Vec2>{
+        return vec![Vec2::new(-20.0, -20.0), Vec2::new(-20.0, 20.0), Vec2::new(20.0, 20.0), Vec2::new(20.0, -20.0)];
+    }
+
+    #[test]
+    fn outline_with_spacing_meshes(){
+        let definition = SBDefinition::from_outline(square(), Some(10.0)).unwrap();
+
+        assert!(definition.outline.is_none());
+        assert!(definition.nodes.len() > 4);
+        assert_eq!(definition.validate(), Ok(()));
+    }
+
+    #[test]
+    fn spacing_has_to_be_above_zero(){
+        for spacing in [0.0, -10.0, f32::NAN, f32::INFINITY]{
+            assert!(matches!(SBDefinition::from_outline(square(), Some(spacing)), Err(SBError::InvalidSpacing{..})));
+
+            // straight from a file, before meshing
+            let mut definition: SBDefinition = ron::from_str("(outline: Some([(-20.0, -20.0), (-20.0, 20.0), (20.0, 20.0), (20.0, -20.0)]))").unwrap();
+            definition.interior_spacing = Some(spacing);
+
+            assert!(matches!(definition.validate(), Err(SBError::InvalidSpacing{..})));
+            assert!(matches!(definition.mesh_outline(), Err(SBError::InvalidSpacing{..})));
+        }
+    }
+
+    #[test]
+    fn reload_keeps_what_the_file_doesnt_know_about(){
+        let definition = SBDefinition::from_outline(square(), Some(10.0)).unwrap();
+
+        let mut old = definition.to_sb().unwrap();
+        old.set_preset(SBPreset::Clay);
+        old.material.charge = 1.0;
+        old.inflate(2.0);
+        // halfway there
+        old.scale = 1.5;
+
+        let new = definition.to_sb_at(&old).unwrap();
+
+        assert_eq!(new.preset, Some(SBPreset::Clay));
+        assert_eq!(new.material.charge, 1.0);
+        assert_eq!(new.material.stiffness, SBPreset::Clay.material().stiffness);
+        assert_eq!((new.scale, new.target_scale), (1.5, 2.0));
+
+        // at the size it had grown to
+        for (new_connection, old_connection) in new.connections.iter().zip(&old.connections){
+            assert!((new_connection.resting_length - old_connection.resting_length * 1.5).abs() < 1e-3);
+        }
+
+        // without a preset the file's material wins
+        let mut plain = definition.to_sb().unwrap();
+        plain.material.stiffness *= 2.0;
+
+        assert_eq!(definition.to_sb_at(&plain).unwrap().material, definition.material);
+    }
+}
@@ -0,0 +1,121 @@
+use bevy::{
+    diagnostic::{Diagnostic, DiagnosticPath, Diagnostics, RegisterDiagnostic},
+    prelude::*,
+};
+
+use crate::settings::*;
+use crate::soft_body::*;
+
+pub const KINETIC_ENERGY: DiagnosticPath = DiagnosticPath::const_new("soft_body/kinetic_energy");
+pub const SPRING_ENERGY: DiagnosticPath = DiagnosticPath::const_new("soft_body/spring_energy");
+pub const SKELETON_ENERGY: DiagnosticPath = DiagnosticPath::const_new("soft_body/skeleton_energy");
+pub const GRAVITY_ENERGY: DiagnosticPath = DiagnosticPath::const_new("soft_body/gravity_energy");
+pub const TOTAL_ENERGY: DiagnosticPath = DiagnosticPath::const_new("soft_body/total_energy");
+pub const LINEAR_MOMENTUM: DiagnosticPath = DiagnosticPath::const_new("soft_body/linear_momentum");
+pub const ANGULAR_MOMENTUM: DiagnosticPath = DiagnosticPath::const_new("soft_body/angular_momentum");
+
+pub struct SBEnergyPlugin;
+
+impl Plugin for SBEnergyPlugin{
+    fn build(&self, app: &mut App){
+        app.init_resource::<SBEnergy>()
+        .register_diagnostic(Diagnostic::new(KINETIC_ENERGY))
+        .register_diagnostic(Diagnostic::new(SPRING_ENERGY))
+        .register_diagnostic(Diagnostic::new(SKELETON_ENERGY))
+        .register_diagnostic(Diagnostic::new(GRAVITY_ENERGY))
+        .register_diagnostic(Diagnostic::new(TOTAL_ENERGY))
+        .register_diagnostic(Diagnostic::new(LINEAR_MOMENTUM))
+        .register_diagnostic(Diagnostic::new(ANGULAR_MOMENTUM))
+        .add_systems(Update, update_energy.after(SBSet::Simulate));
+    }
+}
+
+// every node weighs 1, same as the solver assumes
+#[derive(Default, Clone, Copy, Debug)]
+pub struct EnergyStats{
+    pub kinetic: f32,
+    pub spring: f32,
+    pub skeleton: f32,
+    pub gravitational: f32,
+    pub momentum: Vec2,
+    // about the world origin so the per body values add up to the total
+    pub angular_momentum: f32,
+}
+
+impl EnergyStats{
+    pub fn total_energy(&self) -> f32{
+        return self.kinetic + self.spring + self.skeleton + self.gravitational;
+    }
+
+    fn add(&mut self, other: &EnergyStats){
+        self.kinetic += other.kinetic;
+        self.spring += other.spring;
+        self.skeleton += other.skeleton;
+        self.gravitational += other.gravitational;
+        self.momentum += other.momentum;
+        self.angular_momentum += other.angular_momentum;
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct SBEnergy{
+    pub bodies: Vec<(Entity, EnergyStats)>,
+    pub total: EnergyStats,
+}
+
+pub fn sb_energy(
+    sb: &SB,
+    gravity: Vec2,
+) -> EnergyStats{
+    let mut stats = EnergyStats::default();
+
+    for index in 0..sb.node_num{
+        let node = &sb.nodes[index];
+
+        stats.kinetic += 0.5 * node.mass * node.vel.length_squared();
+
+        // gravity pulls along -gravity, so the floor of the container is the zero level
+        stats.gravitational += node.mass * gravity.dot(node.read_pos - Vec2::new(0.0, -HALF_DIM.y));
+
+        stats.skeleton += 0.5 * sb.material.skeleton_stiffness * (sb.skeleton[index] - node.read_pos).length_squared();
+
+        stats.momentum += node.vel * node.mass;
+        stats.angular_momentum += node.mass * node.read_pos.perp_dot(node.vel);
+    }
+
+    for connection in &sb.connections{
+        let length = (sb.nodes[connection.i2].read_pos - sb.nodes[connection.i1].read_pos).length();
+        let strain = length - connection.resting_length;
+
+        stats.spring += 0.5 * connection.stiffness * strain * strain;
+    }
+
+    return stats;
+}
+
+fn update_energy(
+    sb_query: Query<(Entity, &SB)>,
+    settings: Res<SBSettings>,
+    mut energy: ResMut<SBEnergy>,
+    mut diagnostics: Diagnostics,
+){
+    energy.bodies.clear();
+    energy.total = EnergyStats::default();
+
+    for (entity, sb) in &sb_query{
+        let stats = sb_energy(sb, settings.gravity);
+
+        energy.total.add(&stats);
+        energy.bodies.push((entity, stats));
+    }
+
+    let total = energy.total;
+
+    diagnostics.add_measurement(&KINETIC_ENERGY, || total.kinetic as f64);
+    diagnostics.add_measurement(&SPRING_ENERGY, || total.spring as f64);
+    diagnostics.add_measurement(&SKELETON_ENERGY, || total.skeleton as f64);
+    diagnostics.add_measurement(&GRAVITY_ENERGY, || total.gravitational as f64);
+    diagnostics.add_measurement(&TOTAL_ENERGY, || total.total_energy() as f64);
+    diagnostics.add_measurement(&LINEAR_MOMENTUM, || total.momentum.length() as f64);
+    diagnostics.add_measurement(&ANGULAR_MOMENTUM, || total.angular_momentum as f64);
+}
@@ -0,0 +1,129 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::settings::*;
+use crate::soft_body::*;
+
+// Forces between whole bodies, worked out from their centers: charges push apart when
+// they are alike and pull together when they arent, and with body gravity on every body
+// pulls on every other one by its mass. Both fall off with the distance squared, softened
+// up close and faded out to nothing at FIELD_CUTOFF. The force is shared out over the
+// nodes by mass, so it moves a body without turning it.
+// K goes through the charges for the body under the mouse, I turns body gravity on and off
+pub struct SBFieldPlugin;
+
+impl Plugin for SBFieldPlugin{
+    fn build(&self, app: &mut App){
+        app.add_systems(Update, charge_body.in_set(SBSet::Input));
+    }
+}
+
+// the charges K goes through
+pub const CHARGES: [f32; 3] = [0.0, 1.0, -1.0];
+
+// what update_fields needs from each body
+struct FieldSource{
+    center: Vec2,
+    mass: f32,
+    charge: f32,
+    bounding_box: BoundingBox,
+}
+
+// the force on a body at `offset` from another one, positive pushes it away
+fn field_force(
+    offset_sq: f32,
+    (mass1, charge1): (f32, f32),
+    (mass2, charge2): (f32, f32),
+    body_gravity: f32,
+) -> f32{
+    if offset_sq >= FIELD_CUTOFF * FIELD_CUTOFF{
+        return 0.0;
+    }
+
+    // full strength up to half the cutoff, then smoothly down to 0
+    let t = ((FIELD_CUTOFF * FIELD_CUTOFF - offset_sq) / (FIELD_CUTOFF * FIELD_CUTOFF * 0.75)).min(1.0);
+    let fade = t * t * (3.0 - 2.0 * t);
+
+    return (CHARGE_STRENGTH * charge1 * charge2 - body_gravity * mass1 * mass2) / (offset_sq + FIELD_SOFTENING * FIELD_SOFTENING) * fade;
+}
+
+pub fn update_fields(
+    sb_query: &mut Query<(Entity, &mut SB)>,
+    dt: f32,
+    body_gravity: f32,
+){
+    let sources: Vec<FieldSource> = sb_query.iter().map(|(_, sb)| FieldSource{
+        center: sb.center,
+        mass: sb.nodes.iter().map(|node| node.mass).sum(),
+        charge: sb.material.charge,
+        bounding_box: sb.bounding_box.grown(FIELD_CUTOFF),
+    }).collect();
+
+    // nothing to push or pull with
+    if body_gravity == 0.0 && sources.iter().all(|source| source.charge == 0.0){
+        return;
+    }
+
+    let mut forces = vec![Vec2::ZERO; sources.len()];
+
+    for i in 0..sources.len(){
+        for j in (i + 1)..sources.len(){
+            let (source1, source2) = (&sources[i], &sources[j]);
+
+            if !source1.bounding_box.contains(source2.center){
+                continue;
+            }
+
+            let offset = source1.center - source2.center;
+            let offset_sq = offset.length_squared();
+
+            if offset_sq <= f32::EPSILON{
+                continue;
+            }
+
+            let force = offset / offset_sq.sqrt() * field_force(offset_sq, (source1.mass, source1.charge), (source2.mass, source2.charge), body_gravity);
+
+            forces[i] += force;
+            forces[j] -= force;
+        }
+    }
+
+    // same order as the sources were collected in
+    for ((_, mut sb), (force, source)) in sb_query.iter_mut().zip(forces.iter().zip(&sources)){
+        if *force == Vec2::ZERO || source.mass <= 0.0{
+            continue;
+        }
+
+        let acceleration = *force / source.mass;
+
+        for node in sb.nodes.iter_mut(){
+            node.vel += acceleration * dt * ITERATION_DELTA;
+        }
+    }
+}
+
+// the one after `charge` in CHARGES
+pub fn next_charge(
+    charge: f32,
+) -> f32{
+    return CHARGES.iter().position(|other| *other == charge).map(|index| CHARGES[(index + 1) % CHARGES.len()]).unwrap_or(0.0);
+}
+
+fn charge_body(
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    input: Res<ButtonInput<KeyCode>>,
+    mut input_writer: EventWriter<SBInput>,
+){
+    if !input.just_pressed(KeyCode::KeyK){
+        return;
+    }
+
+    let Some(mouse_position) = q_windows.single().cursor_position() else{
+        return;
+    };
+
+    let mut pos = mouse_position - HALF_DIM;
+    pos.y = -pos.y;
+
+    input_writer.send(SBInput::Charge{pos});
+}
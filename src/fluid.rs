@@ -0,0 +1,366 @@
+use bevy::{
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+    utils::HashMap,
+};
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::*;
+use crate::soft_body::*;
+use crate::contact::*;
+
+// Smoothed particle hydrodynamics: every particle carries a bit of the fluid, and the
+// density, pressure and viscosity at it come from the particles within FLUID_SMOOTHING,
+// weighted by kernels that fall off to 0 there. The particles find each other through a
+// grid of FLUID_SMOOTHING sized cells.
+// Particles that end up inside a soft body get pushed out through the closest edge and
+// the edge gets pushed back the other way, which is what makes bodies float and slosh.
+// W pours a block of fluid at the mouse
+pub struct SBFluidPlugin;
+
+impl Plugin for SBFluidPlugin{
+    fn build(&self, app: &mut App){
+        app.add_systems(Update, pour_fluid.in_set(SBSet::Input))
+        .add_systems(Update, (add_particle_draws, update_fluid_draw).chain().after(SBSet::Simulate));
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FluidParticle{
+    pub pos: Vec2,
+    pub vel: Vec2,
+    // recomputed with the forces
+    #[serde(skip)]
+    density: f32,
+    #[serde(skip)]
+    pressure: f32,
+    // from the pressure and viscosity, kept between force updates
+    #[serde(skip)]
+    acceleration: Vec2,
+}
+
+impl FluidParticle{
+    pub fn new(pos: Vec2) -> Self{
+        Self{pos, vel: Vec2::ZERO, density: 0.0, pressure: 0.0, acceleration: Vec2::ZERO}
+    }
+}
+
+// all the fluid in the world, one entity so the particles can see each other
+#[derive(Component)]
+pub struct Fluid{
+    pub particles: Vec<FluidParticle>,
+    rest_density: f32,
+    // particle indices by cell
+    grid: HashMap<IVec2, Vec<usize>>,
+}
+
+#[derive(Component)]
+struct ParticleIndex(usize);
+
+// poly6, for the density
+fn kernel(
+    dist_sq: f32,
+) -> f32{
+    let h_sq = FLUID_SMOOTHING * FLUID_SMOOTHING;
+
+    if dist_sq >= h_sq{
+        return 0.0;
+    }
+
+    return 4.0 / (PI * h_sq.powi(4)) * (h_sq - dist_sq).powi(3);
+}
+
+// spiky, for the pressure. Doesnt flatten out up close so particles dont clump
+fn kernel_gradient(
+    offset: Vec2,
+    dist: f32,
+) -> Vec2{
+    if dist >= FLUID_SMOOTHING || dist <= f32::EPSILON{
+        return Vec2::ZERO;
+    }
+
+    return offset / dist * (-30.0 / (PI * FLUID_SMOOTHING.powi(5)) * (FLUID_SMOOTHING - dist).powi(2));
+}
+
+// for the viscosity
+fn kernel_laplacian(
+    dist: f32,
+) -> f32{
+    if dist >= FLUID_SMOOTHING{
+        return 0.0;
+    }
+
+    return 40.0 / (PI * FLUID_SMOOTHING.powi(5)) * (FLUID_SMOOTHING - dist);
+}
+
+fn grid_cell(
+    pos: Vec2,
+) -> IVec2{
+    return (pos / FLUID_SMOOTHING).floor().as_ivec2();
+}
+
+// a block of particles around `center`, every other row shifted by half a spacing
+pub fn fluid_block(
+    center: Vec2,
+    size: usize,
+) -> Vec<Vec2>{
+    let corner = center - Vec2::splat((size - 1) as f32 * FLUID_SPACING / 2.0);
+
+    let mut points = Vec::new();
+
+    for row in 0..size{
+        let shift = if row % 2 == 1 {FLUID_SPACING / 2.0} else {0.0};
+
+        for column in 0..size{
+            points.push(corner + Vec2::new(column as f32 * FLUID_SPACING + shift, row as f32 * FLUID_SPACING));
+        }
+    }
+
+    return points;
+}
+
+impl Fluid{
+    pub fn new(particles: Vec<FluidParticle>) -> Self{
+        // the density in the middle of a freshly poured block, which the fluid tries to keep
+        let block = fluid_block(Vec2::ZERO, 9);
+        let middle = block[block.len() / 2];
+
+        let rest_density = block.iter().map(|pt| FLUID_PARTICLE_MASS * kernel(pt.distance_squared(middle))).sum();
+
+        Self{particles, rest_density, grid: HashMap::new()}
+    }
+
+    fn update_grid(&mut self){
+        self.grid.clear();
+
+        for (index, particle) in self.particles.iter().enumerate(){
+            self.grid.entry(grid_cell(particle.pos)).or_default().push(index);
+        }
+    }
+
+    fn neighbours(&self, pos: Vec2) -> impl Iterator<Item = usize> + '_{
+        let cell = grid_cell(pos);
+
+        return (-1..=1).flat_map(move |x| (-1..=1).map(move |y| cell + IVec2::new(x, y)))
+            .filter_map(|cell| self.grid.get(&cell))
+            .flatten()
+            .copied();
+    }
+
+    fn update_densities(&mut self){
+        for i in 0..self.particles.len(){
+            let pos = self.particles[i].pos;
+
+            let density: f32 = self.neighbours(pos).map(|j| FLUID_PARTICLE_MASS * kernel(pos.distance_squared(self.particles[j].pos))).sum();
+
+            let particle = &mut self.particles[i];
+
+            particle.density = density;
+            // only pushes, pulling makes the surface clump up
+            particle.pressure = FLUID_STIFFNESS * (density - self.rest_density).max(0.0);
+        }
+    }
+
+    fn update_accelerations(&mut self){
+        let mut accelerations = vec![Vec2::ZERO; self.particles.len()];
+
+        for (i, particle) in self.particles.iter().enumerate(){
+            if particle.density <= 0.0{
+                continue;
+            }
+
+            for j in self.neighbours(particle.pos){
+                if j == i{
+                    continue;
+                }
+
+                let other = &self.particles[j];
+
+                let offset = particle.pos - other.pos;
+                let dist = offset.length();
+
+                if dist >= FLUID_SMOOTHING || other.density <= 0.0{
+                    continue;
+                }
+
+                let pressure = -FLUID_PARTICLE_MASS * (particle.pressure + other.pressure) / (2.0 * other.density) * kernel_gradient(offset, dist);
+                let viscosity = FLUID_VISCOSITY * FLUID_PARTICLE_MASS / other.density * (other.vel - particle.vel) * kernel_laplacian(dist);
+
+                accelerations[i] += pressure / particle.density + viscosity;
+            }
+        }
+
+        for (particle, acceleration) in self.particles.iter_mut().zip(accelerations){
+            particle.acceleration = acceleration;
+        }
+    }
+}
+
+// `substep` counts the substeps in the frame, the forces only get updated every FLUID_FORCE_INTERVAL
+pub fn update_fluid(
+    fluid_query: &mut Query<&mut Fluid>,
+    dt: f32,
+    gravity: Vec2,
+    substep: i32,
+){
+    for mut fluid in fluid_query.iter_mut(){
+        if substep % FLUID_FORCE_INTERVAL == 0{
+            fluid.update_grid();
+            fluid.update_densities();
+            fluid.update_accelerations();
+        }
+
+        for particle in fluid.particles.iter_mut(){
+            particle.vel += (particle.acceleration - gravity) * dt * ITERATION_DELTA;
+            particle.pos += particle.vel * dt * ITERATION_DELTA;
+
+            fluid_container_collision(particle);
+        }
+    }
+}
+
+// same walls as the soft bodies get
+fn fluid_container_collision(
+    particle: &mut FluidParticle,
+){
+    if particle.pos.y < -HALF_DIM.y{
+        particle.pos.y = -HALF_DIM.y;
+        particle.vel.y = 0.0;
+    }
+
+    if particle.pos.x > HALF_DIM.x{
+        particle.pos.x = HALF_DIM.x;
+        particle.vel.x = 0.0;
+    }
+
+    else if particle.pos.x < -HALF_DIM.x{
+        particle.pos.x = -HALF_DIM.x;
+        particle.vel.x = 0.0;
+    }
+}
+
+// pushes particles inside soft bodies out through the closest edge, the edge takes the
+// other side of it. Particles dont have a body of their own, so the point across from
+// the soft body's center stands in for it when picking the edge
+pub fn update_fluid_collisions(
+    sb_query: &mut Query<(Entity, &mut SB)>,
+    fluid_query: &mut Query<&mut Fluid>,
+){
+    let particle_inverse_mass = 1.0 / FLUID_PARTICLE_MASS;
+
+    for mut fluid in fluid_query.iter_mut(){
+        for (_, mut sb) in sb_query.iter_mut(){
+            for particle in fluid.particles.iter_mut(){
+                if !sb.bounding_box.contains(particle.pos) || !sb_point_intersection(particle.pos, &sb){
+                    continue;
+                }
+
+                let (edge_pt, dist, edge, t) = get_closest_edge(particle.pos, particle.pos * 2.0 - sb.center, &sb);
+                let connection = &sb.connections[edge];
+
+                // nothing faces the outside point, or it is right on the edge already. Unlike
+                // nodes, particles can end up deep inside, so there is no limit the other way
+                if dist >= sb.bounding_box.max_pos.distance(sb.bounding_box.min_pos) || dist <= f32::EPSILON{
+                    continue;
+                }
+
+                let t = t.clamp(0.0, 1.0);
+                let point = [(connection.i1, 1.0 - t), (connection.i2, t)];
+
+                let normal = (edge_pt - particle.pos) / dist;
+                let inverse_mass = particle_inverse_mass + contact_inverse_mass(&sb, &point);
+
+                let push = dist / inverse_mass;
+
+                // fluid doesnt bounce, the speed into the edge just goes
+                let approach = (particle.vel - contact_vel(&sb, &point)).dot(normal);
+                let impulse = if approach < 0.0 {-approach / inverse_mass} else {0.0};
+
+                particle.pos += normal * push * particle_inverse_mass;
+                particle.vel += normal * impulse * particle_inverse_mass;
+
+                apply_contact_point(&mut sb, &point, -normal * push, -normal * impulse);
+            }
+        }
+    }
+}
+
+pub fn spawn_fluid_entity(
+    commands: &mut Commands,
+    particles: Vec<FluidParticle>,
+) -> Entity{
+    return commands.spawn((SpatialBundle::default(), Fluid::new(particles), Name::new("Fluid"))).id();
+}
+
+fn pour_fluid(
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    input: Res<ButtonInput<KeyCode>>,
+    mut input_writer: EventWriter<SBInput>,
+){
+    if !input.just_pressed(KeyCode::KeyW){
+        return;
+    }
+
+    let Some(mouse_position) = q_windows.single().cursor_position() else{
+        return;
+    };
+
+    let mut pos = mouse_position - HALF_DIM;
+    pos.y = -pos.y;
+
+    input_writer.send(SBInput::SpawnFluid{particles: fluid_block(pos, FLUID_BLOCK)});
+}
+
+// one circle per particle, added as the fluid grows
+fn add_particle_draws(
+    mut commands: Commands,
+    fluid_query: Query<(Entity, &Fluid, Option<&Children>)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut handles: Local<Option<(Mesh2dHandle, Handle<ColorMaterial>)>>,
+){
+    for (entity, fluid, children) in &fluid_query{
+        let drawn = children.map(|children| children.len()).unwrap_or(0);
+
+        if drawn >= fluid.particles.len(){
+            continue;
+        }
+
+        let (mesh, material) = handles.get_or_insert_with(|| (
+            Mesh2dHandle(meshes.add(Circle::new(FLUID_SPACING / 2.0))),
+            materials.add(Color::srgb(0.2, 0.4, 0.9)),
+        )).clone();
+
+        commands.entity(entity).with_children(|parent|{
+            for index in drawn..fluid.particles.len(){
+                parent.spawn((
+                    MaterialMesh2dBundle{
+                        mesh: mesh.clone(),
+                        material: material.clone(),
+                        transform: Transform::from_translation(fluid.particles[index].pos.extend(-0.5)),
+                        ..default()
+                    },
+                    ParticleIndex(index),
+                ));
+            }
+        });
+    }
+}
+
+fn update_fluid_draw(
+    fluid_query: Query<(&Fluid, &Children)>,
+    mut particle_query: Query<(&mut Transform, &ParticleIndex)>,
+){
+    for (fluid, children) in &fluid_query{
+        for child in children{
+            let Ok((mut transform, index)) = particle_query.get_mut(*child) else{
+                continue;
+            };
+
+            if let Some(particle) = fluid.particles.get(index.0){
+                transform.translation = particle.pos.extend(-0.5);
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use bevy::prelude::*;
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::window::PrimaryWindow;
+
+use crate::settings::*;
+use crate::soft_body::*;
+
+// Blows up or lets down the body under the mouse by scaling its rest shape, the springs
+// and the skeleton both. It grows into the new size over time instead of jumping to it,
+// so it pushes whatever is around it out of the way, or gets squashed by it.
+// The scroll wheel inflates and deflates by INFLATE_SCROLL_STEP a notch, holding = or -
+// keeps going at INFLATE_HOLD_RATE a second for as long as it is held
+pub struct SBInflatePlugin;
+
+impl Plugin for SBInflatePlugin{
+    fn build(&self, app: &mut App){
+        app.add_systems(Update, inflate_body.in_set(SBSet::Input));
+    }
+}
+
+// trackpads scroll by pixels, about this many make a notch
+const PIXELS_PER_NOTCH: f32 = 20.0;
+
+fn inflate_body(
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    time: Res<Time>,
+    input: Res<ButtonInput<KeyCode>>,
+    mut scroll_reader: EventReader<MouseWheel>,
+    mut input_writer: EventWriter<SBInput>,
+){
+    let notches: f32 = scroll_reader.read().map(|scroll| match scroll.unit{
+        MouseScrollUnit::Line => scroll.y,
+        MouseScrollUnit::Pixel => scroll.y / PIXELS_PER_NOTCH,
+    }).sum();
+
+    // everything from this frame goes in one input
+    let mut factor = INFLATE_SCROLL_STEP.powf(notches);
+    let hold = INFLATE_HOLD_RATE.powf(time.delta_seconds());
+
+    if input.pressed(KeyCode::Equal){
+        factor *= hold;
+    }
+
+    if input.pressed(KeyCode::Minus){
+        factor /= hold;
+    }
+
+    if factor == 1.0{
+        return;
+    }
+
+    let Some(mouse_position) = q_windows.single().cursor_position() else{
+        return;
+    };
+
+    let mut pos = mouse_position - HALF_DIM;
+    pos.y = -pos.y;
+
+    input_writer.send(SBInput::Inflate{pos, factor});
+}
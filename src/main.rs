@@ -1,19 +1,59 @@
 use bevy::{
     color::palettes::css::GOLD,
-    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    diagnostic::{DiagnosticPath, DiagnosticsStore, FrameTimeDiagnosticsPlugin},
     prelude::*,
 };
 
 use settings::*;
 use soft_body::*;
+use energy::*;
+use replay::*;
+use snapshot::*;
+use definition::*;
+use svg::*;
+use sprite::*;
+use rigid::*;
+use fluid::*;
+use material::*;
+use water::*;
+use field::*;
+use slice::*;
+use inflate::*;
 
 // CRATES
 mod settings;
 mod soft_body;
+mod energy;
+mod replay;
+mod snapshot;
+mod definition;
+mod shapes;
+mod meshing;
+mod svg;
+mod sprite;
+mod contact;
+mod rigid;
+mod fluid;
+mod material;
+mod water;
+mod field;
+mod slice;
+mod inflate;
 
 #[derive(Component)]
 struct FpsText;
 
+// label and diagnostic for every line of the overlay under the fps counter
+const ENERGY_LINES: [(&str, DiagnosticPath); 7] = [
+    ("Kinetic: ", KINETIC_ENERGY),
+    ("Spring: ", SPRING_ENERGY),
+    ("Skeleton: ", SKELETON_ENERGY),
+    ("Gravity: ", GRAVITY_ENERGY),
+    ("Total: ", TOTAL_ENERGY),
+    ("Momentum: ", LINEAR_MOMENTUM),
+    ("Angular: ", ANGULAR_MOMENTUM),
+];
+
 
 fn main() {
     App::new()
@@ -35,6 +75,19 @@ fn main() {
     .add_systems(Startup, setup)
     .add_systems(Update, text_update_system)
     .add_plugins(SBPlugin)
+    .add_plugins(SBEnergyPlugin)
+    .add_plugins(SBReplayPlugin)
+    .add_plugins(SBSnapshotPlugin)
+    .add_plugins(SBDefinitionPlugin)
+    .add_plugins(SBSvgPlugin)
+    .add_plugins(SBSpritePlugin)
+    .add_plugins(SBRigidPlugin)
+    .add_plugins(SBFluidPlugin)
+    .add_plugins(SBMaterialPlugin)
+    .add_plugins(SBWaterPlugin)
+    .add_plugins(SBFieldPlugin)
+    .add_plugins(SBSlicePlugin)
+    .add_plugins(SBInflatePlugin)
     .run();
 }
 
@@ -57,23 +110,42 @@ fn setup(mut commands: Commands,
     let camera = Camera2dBundle::default();
     commands.spawn(camera);    
 
+    let mut sections = vec![
+        TextSection::new(
+            "FPS: ",
+            TextStyle {
+                font_size: 30.0,
+                ..default()
+            },
+        ),
+        TextSection::from_style(
+            TextStyle {
+                font_size: 30.0,
+                color: GOLD.into(),
+                ..default()
+            }
+        ),
+    ];
+
+    for (label, _) in &ENERGY_LINES{
+        sections.push(TextSection::new(
+            format!("\n{}", label),
+            TextStyle {
+                font_size: 15.0,
+                ..default()
+            },
+        ));
+        sections.push(TextSection::from_style(
+            TextStyle {
+                font_size: 15.0,
+                color: GOLD.into(),
+                ..default()
+            }
+        ));
+    }
+
     commands.spawn((
-        TextBundle::from_sections([
-            TextSection::new(
-                "FPS: ",
-                TextStyle {
-                    font_size: 30.0,
-                    ..default()
-                },
-            ),
-            TextSection::from_style(
-                TextStyle {
-                    font_size: 30.0,
-                    color: GOLD.into(),
-                    ..default()
-                }
-            ),
-        ]),
+        TextBundle::from_sections(sections),
         FpsText,
     ));
 }
@@ -93,4 +165,10 @@ fn text_update_system(
         }
     }
 
+    for (counter, (_, path)) in ENERGY_LINES.iter().enumerate(){
+        if let Some(value) = diagnostics.get(path).and_then(|diagnostic| diagnostic.value()) {
+            fps_text.sections[3 + counter * 2].value = format!("{:.1}", value);
+        }
+    }
+
 }
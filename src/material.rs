@@ -0,0 +1,152 @@
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::*;
+use crate::soft_body::*;
+
+// Named materials, so trying out a different feel doesnt mean changing the
+// defaults in settings.rs. M goes through the presets space spawns with, the
+// first stop being none which leaves every shape its own material. N gives
+// the body under the mouse the selected one
+pub struct SBMaterialPlugin;
+
+impl Plugin for SBMaterialPlugin{
+    fn build(&self, app: &mut App){
+        app.init_resource::<SBPresetSelection>()
+        .add_systems(Update, (select_preset, apply_preset).in_set(SBSet::Input));
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SBPreset{
+    Rubber,
+    Jelly,
+    Foam,
+    StiffPlastic,
+    Clay,
+}
+
+impl SBPreset{
+    pub const ALL: [SBPreset; 5] = [SBPreset::Rubber, SBPreset::Jelly, SBPreset::Foam, SBPreset::StiffPlastic, SBPreset::Clay];
+
+    pub fn name(&self) -> &'static str{
+        return match self{
+            SBPreset::Rubber => "rubber",
+            SBPreset::Jelly => "jelly",
+            SBPreset::Foam => "foam",
+            SBPreset::StiffPlastic => "stiff plastic",
+            SBPreset::Clay => "clay",
+        };
+    }
+
+    pub fn material(&self) -> SBMaterial{
+        return match self{
+            // springy and grippy, bounces off everything
+            SBPreset::Rubber => SBMaterial{
+                stiffness: 40.0,
+                dampening: 2.0,
+                skeleton_stiffness: 15.0,
+                friction: 0.8,
+                restitution: 0.6,
+                density: 1.0,
+                plasticity: 0.0,
+                charge: 0.0,
+            },
+            // wobbles for a long time and barely holds its shape
+            SBPreset::Jelly => SBMaterial{
+                stiffness: 12.0,
+                dampening: 1.5,
+                skeleton_stiffness: 4.0,
+                friction: 0.3,
+                restitution: 0.2,
+                density: 1.0,
+                plasticity: 0.0,
+                charge: 0.0,
+            },
+            // light, squashes easily and soaks up hits
+            SBPreset::Foam => SBMaterial{
+                stiffness: 15.0,
+                dampening: 8.0,
+                skeleton_stiffness: 6.0,
+                friction: 0.6,
+                restitution: 0.05,
+                density: 0.3,
+                plasticity: 0.0,
+                charge: 0.0,
+            },
+            SBPreset::StiffPlastic => SBMaterial{
+                stiffness: 120.0,
+                dampening: 10.0,
+                skeleton_stiffness: 60.0,
+                friction: 0.3,
+                restitution: 0.3,
+                density: 1.2,
+                plasticity: 0.0,
+                charge: 0.0,
+            },
+            // heavy and dead, keeps the dents it gets
+            SBPreset::Clay => SBMaterial{
+                stiffness: 30.0,
+                dampening: 10.0,
+                skeleton_stiffness: 5.0,
+                friction: 0.9,
+                restitution: 0.0,
+                density: 1.5,
+                plasticity: 0.5,
+                charge: 0.0,
+            },
+        };
+    }
+}
+
+// the preset new bodies get, none keeps the one from the shape
+#[derive(Resource, Default)]
+pub struct SBPresetSelection(pub Option<SBPreset>);
+
+fn select_preset(
+    input: Res<ButtonInput<KeyCode>>,
+    mut selection: ResMut<SBPresetSelection>,
+){
+    if !input.just_pressed(KeyCode::KeyM){
+        return;
+    }
+
+    // none, then every preset in order
+    let next = match selection.0{
+        None => 0,
+        Some(preset) => SBPreset::ALL.iter().position(|other| *other == preset).unwrap_or(0) + 1,
+    };
+
+    selection.0 = SBPreset::ALL.get(next).copied();
+
+    match selection.0{
+        Some(preset) => info!("Spawning {}", preset.name()),
+        None => info!("Spawning with the shape's own material"),
+    }
+}
+
+fn apply_preset(
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    input: Res<ButtonInput<KeyCode>>,
+    selection: Res<SBPresetSelection>,
+    mut input_writer: EventWriter<SBInput>,
+){
+    if !input.just_pressed(KeyCode::KeyN){
+        return;
+    }
+
+    let Some(preset) = selection.0 else{
+        warn!("No preset selected, M picks one");
+        return;
+    };
+
+    let Some(mouse_position) = q_windows.single().cursor_position() else{
+        return;
+    };
+
+    let mut pos = mouse_position - HALF_DIM;
+    pos.y = -pos.y;
+
+    input_writer.send(SBInput::SetPreset{pos, preset});
+}
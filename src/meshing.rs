@@ -0,0 +1,729 @@
+use bevy::prelude::*;
+use std::collections::{HashMap, HashSet};
+
+use crate::soft_body::*;
+
+// Turns a closed outline into a soft body: ear clipping gives a first
+// triangulation, optional interior (steiner) points get dropped into it and
+// edge flips then make it a constrained delaunay triangulation. The outline
+// edges are the constraints and never get flipped away.
+
+const EPSILON: f64 = 1e-9;
+
+// bound on the rounding error of the plain f64 determinant in orient, as a share of
+// the sizes of its two products (Shewchuk's ccwerrboundA)
+const ORIENT_ERROR_BOUND: f64 = (3.0 + 16.0 * (f64::EPSILON / 2.0)) * (f64::EPSILON / 2.0);
+
+// a + b as the rounded sum and the error that rounding made, which together are exact
+fn two_sum(
+    a: f64,
+    b: f64,
+) -> (f64, f64){
+    let sum = a + b;
+    let b_virtual = sum - a;
+    let a_virtual = sum - b_virtual;
+
+    return (sum, (a - a_virtual) + (b - b_virtual));
+}
+
+// > 0 when a b c turn counter clockwise, 0 only when they are exactly on a line.
+// The plain f64 determinant gets the sign right unless it is within rounding error of
+// 0, then it is summed up again exactly. Multiplying out the differences leaves six
+// products of two f32 values, which f64 holds exactly, and those get added up as a
+// list of non overlapping parts (Shewchuk's expansions). The biggest part is bigger
+// than all the others together, so it has the sign of the exact result and is close to it
+pub fn orient(
+    a: Vec2,
+    b: Vec2,
+    c: Vec2,
+) -> f64{
+    let (ax, ay) = (a.x as f64, a.y as f64);
+    let (bx, by) = (b.x as f64, b.y as f64);
+    let (cx, cy) = (c.x as f64, c.y as f64);
+
+    let left = (bx - ax) * (cy - ay);
+    let right = (by - ay) * (cx - ax);
+    let det = left - right;
+
+    if det.abs() > ORIENT_ERROR_BOUND * (left.abs() + right.abs()){
+        return det;
+    }
+
+    let products = [bx * cy, -bx * ay, -ax * cy, -by * cx, by * ax, ay * cx];
+
+    // smallest part first
+    let mut parts: Vec<f64> = Vec::with_capacity(products.len() + 1);
+
+    for product in products{
+        let mut carry = product;
+        let mut grown = Vec::with_capacity(parts.len() + 1);
+
+        for part in &parts{
+            let (sum, error) = two_sum(carry, *part);
+            carry = sum;
+
+            if error != 0.0{
+                grown.push(error);
+            }
+        }
+
+        if carry != 0.0{
+            grown.push(carry);
+        }
+
+        parts = grown;
+    }
+
+    // the biggest part comes last
+    return parts.last().copied().unwrap_or(0.0);
+}
+
+// > 0 when d is inside the circle through the counter clockwise triangle a b c
+fn in_circle(
+    a: Vec2,
+    b: Vec2,
+    c: Vec2,
+    d: Vec2,
+) -> f64{
+    let (adx, ady) = ((a.x - d.x) as f64, (a.y - d.y) as f64);
+    let (bdx, bdy) = ((b.x - d.x) as f64, (b.y - d.y) as f64);
+    let (cdx, cdy) = ((c.x - d.x) as f64, (c.y - d.y) as f64);
+
+    let ad = adx * adx + ady * ady;
+    let bd = bdx * bdx + bdy * bdy;
+    let cd = cdx * cdx + cdy * cdy;
+
+    return adx * (bdy * cd - bd * cdy) - ady * (bdx * cd - bd * cdx) + ad * (bdx * cdy - bdy * cdx);
+}
+
+pub fn signed_area(
+    outline: &[Vec2],
+) -> f32{
+    let mut area = 0.0;
+
+    for i in 0..outline.len(){
+        let p1 = outline[i];
+        let p2 = outline[(i + 1) % outline.len()];
+
+        area += p1.perp_dot(p2);
+    }
+
+    return area / 2.0;
+}
+
+// how often the edges wind around pt, counter clockwise counts up. Points on a
+// vertex or a horizontal edge always land on the same side thanks to the half open
+// test on y, and orient never puts a point next to an edge on the wrong side of it
+pub fn winding_number(
+    pt: Vec2,
+    edges: impl Iterator<Item = (Vec2, Vec2)>,
+) -> i32{
+    let mut winding = 0;
+
+    for (p1, p2) in edges{
+        if p1.y <= pt.y{
+            // going up with pt on the left
+            if p2.y > pt.y && orient(p1, p2, pt) > 0.0{
+                winding += 1;
+            }
+        }
+        // going down with pt on the right
+        else if p2.y <= pt.y && orient(p1, p2, pt) < 0.0{
+            winding -= 1;
+        }
+    }
+
+    return winding;
+}
+
+pub fn point_in_outline(
+    pt: Vec2,
+    outline: &[Vec2],
+) -> bool{
+    let edges = (0..outline.len()).map(|i| (outline[i], outline[(i + 1) % outline.len()]));
+
+    return winding_number(pt, edges) != 0;
+}
+
+// evenly spaced points along a closed outline, as close to `spacing` apart as fits
+pub fn resample_outline(
+    outline: &[Vec2],
+    spacing: f32,
+) -> Vec<Vec2>{
+    let points = clean_outline(outline);
+
+    // there is no telling how many points a spacing of 0 would take
+    if points.len() < 3 || !(spacing > 0.0 && spacing.is_finite()){
+        return points;
+    }
+
+    let mut perimeter = 0.0;
+
+    for i in 0..points.len(){
+        perimeter += points[i].distance(points[(i + 1) % points.len()]);
+    }
+
+    let count = ((perimeter / spacing).round() as usize).max(3);
+    let step = perimeter / (count as f32);
+
+    let mut resampled = Vec::with_capacity(count);
+
+    let mut segment = 0;
+    let mut segment_start = 0.0;
+
+    for k in 0..count{
+        let target = step * (k as f32);
+
+        loop{
+            let p1 = points[segment];
+            let p2 = points[(segment + 1) % points.len()];
+            let length = p1.distance(p2);
+
+            if target <= segment_start + length || segment == points.len() - 1{
+                let t = if length > 0.0 {((target - segment_start) / length).clamp(0.0, 1.0)} else {0.0};
+
+                resampled.push(p1.lerp(p2, t));
+                break;
+            }
+
+            segment_start += length;
+            segment += 1;
+        }
+    }
+
+    return resampled;
+}
+
+// ramer douglas peucker on a closed outline, drops points that are less than
+// `tolerance` away from the line through the ones that are kept
+pub fn simplify_outline(
+    outline: &[Vec2],
+    tolerance: f32,
+) -> Vec<Vec2>{
+    let points = clean_outline(outline);
+
+    if points.len() < 4{
+        return points;
+    }
+
+    // the point furthest from the first one is surely a corner, split the loop there
+    let far = (1..points.len()).max_by(|&a, &b| points[0].distance_squared(points[a]).total_cmp(&points[0].distance_squared(points[b]))).unwrap();
+
+    let mut keep = vec![false; points.len()];
+    keep[0] = true;
+    keep[far] = true;
+
+    let mut stack = vec![(0, far), (far, points.len())];
+
+    while let Some((start, end)) = stack.pop(){
+        let p1 = points[start];
+        let p2 = points[end % points.len()];
+
+        let mut furthest = None;
+        let mut furthest_dist = tolerance;
+
+        for i in (start + 1)..end{
+            let dist = segment_dist(points[i], p1, p2);
+
+            if dist > furthest_dist{
+                furthest = Some(i);
+                furthest_dist = dist;
+            }
+        }
+
+        if let Some(i) = furthest{
+            keep[i] = true;
+            stack.push((start, i));
+            stack.push((i, end));
+        }
+    }
+
+    return points.iter().zip(keep).filter(|(_, keep)| *keep).map(|(pt, _)| *pt).collect();
+}
+
+fn segment_dist(
+    pt: Vec2,
+    p1: Vec2,
+    p2: Vec2,
+) -> f32{
+    let ab = p2 - p1;
+    let t = ((pt - p1).dot(ab) / ab.length_squared().max(f32::EPSILON)).clamp(0.0, 1.0);
+
+    return pt.distance(p1 + ab * t);
+}
+
+fn edge_key(
+    i1: usize,
+    i2: usize,
+) -> (usize, usize){
+    return (i1.min(i2), i1.max(i2));
+}
+
+// drops repeated points, including a closing point equal to the first one
+pub fn clean_outline(
+    outline: &[Vec2],
+) -> Vec<Vec2>{
+    let mut points: Vec<Vec2> = Vec::new();
+
+    for pt in outline{
+        if points.last().map_or(true, |last| last.distance_squared(*pt) > 1e-6){
+            points.push(*pt);
+        }
+    }
+
+    while points.len() > 1 && points[0].distance_squared(*points.last().unwrap()) <= 1e-6{
+        points.pop();
+    }
+
+    return points;
+}
+
+// outline has to be counter clockwise, gives counter clockwise triangles
+fn ear_clip(
+    points: &[Vec2],
+) -> Vec<[usize; 3]>{
+    let mut remaining: Vec<usize> = (0..points.len()).collect();
+    let mut triangles = Vec::new();
+
+    while remaining.len() > 3{
+        let count = remaining.len();
+        let mut clipped = None;
+
+        // a strict pass first, then allow flat ears so collinear points cant get us stuck
+        for strict in [true, false]{
+            for i in 0..count{
+                let prev = remaining[(i + count - 1) % count];
+                let cur = remaining[i];
+                let next = remaining[(i + 1) % count];
+
+                let turn = orient(points[prev], points[cur], points[next]);
+
+                if turn < -EPSILON || (strict && turn <= EPSILON){
+                    continue;
+                }
+
+                let blocked = remaining.iter().any(|&other| {
+                    if other == prev || other == cur || other == next{
+                        return false;
+                    }
+
+                    let pt = points[other];
+
+                    orient(points[prev], points[cur], pt) >= -EPSILON
+                        && orient(points[cur], points[next], pt) >= -EPSILON
+                        && orient(points[next], points[prev], pt) >= -EPSILON
+                });
+
+                if !blocked{
+                    clipped = Some(i);
+                    break;
+                }
+            }
+
+            if clipped.is_some(){
+                break;
+            }
+        }
+
+        // self intersecting outline, cut whatever is there
+        let i = clipped.unwrap_or(0);
+
+        let prev = remaining[(i + count - 1) % count];
+        let cur = remaining[i];
+        let next = remaining[(i + 1) % count];
+
+        if orient(points[prev], points[cur], points[next]).abs() > EPSILON{
+            triangles.push([prev, cur, next]);
+        }
+
+        remaining.remove(i);
+    }
+
+    if remaining.len() == 3 && orient(points[remaining[0]], points[remaining[1]], points[remaining[2]]).abs() > EPSILON{
+        triangles.push([remaining[0], remaining[1], remaining[2]]);
+    }
+
+    return triangles;
+}
+
+struct Triangulation{
+    points: Vec<Vec2>,
+    triangles: Vec<[usize; 3]>,
+    edges: HashMap<(usize, usize), Vec<usize>>,
+}
+
+impl Triangulation{
+    fn new(points: Vec<Vec2>, triangles: Vec<[usize; 3]>) -> Self{
+        let mut triangulation = Triangulation{points, triangles: Vec::new(), edges: HashMap::new()};
+
+        for triangle in triangles{
+            triangulation.add_triangle(triangle);
+        }
+
+        return triangulation;
+    }
+
+    fn add_triangle(&mut self, triangle: [usize; 3]){
+        let index = self.triangles.len();
+        self.triangles.push(triangle);
+
+        for k in 0..3{
+            self.edges.entry(edge_key(triangle[k], triangle[(k + 1) % 3])).or_default().push(index);
+        }
+    }
+
+    fn set_triangle(&mut self, index: usize, triangle: [usize; 3]){
+        let old = self.triangles[index];
+
+        for k in 0..3{
+            if let Some(list) = self.edges.get_mut(&edge_key(old[k], old[(k + 1) % 3])){
+                list.retain(|&t| t != index);
+            }
+        }
+
+        self.triangles[index] = triangle;
+
+        for k in 0..3{
+            self.edges.entry(edge_key(triangle[k], triangle[(k + 1) % 3])).or_default().push(index);
+        }
+    }
+
+    // splits whichever triangle holds pt, or the two next to the edge it sits on
+    fn insert(&mut self, pt: Vec2){
+        for index in 0..self.triangles.len(){
+            let [a, b, c] = self.triangles[index];
+            let corners = [self.points[a], self.points[b], self.points[c]];
+
+            let sides = [
+                orient(corners[0], corners[1], pt),
+                orient(corners[1], corners[2], pt),
+                orient(corners[2], corners[0], pt),
+            ];
+
+            if sides.iter().any(|side| *side < -EPSILON){
+                continue;
+            }
+
+            let new_index = self.points.len();
+            self.points.push(pt);
+
+            let tri = [a, b, c];
+
+            if let Some(k) = (0..3).find(|&k| sides[k].abs() <= EPSILON){
+                // on the edge tri[k] -> tri[k + 1]
+                let e1 = tri[k];
+                let e2 = tri[(k + 1) % 3];
+                let opposite = tri[(k + 2) % 3];
+
+                let neighbour = self.edges.get(&edge_key(e1, e2)).and_then(|list| list.iter().copied().find(|&t| t != index));
+
+                self.set_triangle(index, [e1, new_index, opposite]);
+                self.add_triangle([new_index, e2, opposite]);
+
+                if let Some(neighbour) = neighbour{
+                    let other = self.triangles[neighbour].iter().copied().find(|&v| v != e1 && v != e2).unwrap();
+
+                    self.set_triangle(neighbour, [e2, new_index, other]);
+                    self.add_triangle([new_index, e1, other]);
+                }
+            }
+            else{
+                self.set_triangle(index, [a, b, new_index]);
+                self.add_triangle([b, c, new_index]);
+                self.add_triangle([c, a, new_index]);
+            }
+
+            return;
+        }
+    }
+
+    // lawson flips until every edge that isnt a constraint is locally delaunay
+    fn make_delaunay(&mut self, constrained: &dyn Fn(usize, usize) -> bool){
+        let mut flipped = true;
+        let mut passes = 0;
+
+        while flipped && passes < 1000{
+            flipped = false;
+            passes += 1;
+
+            let keys: Vec<(usize, usize)> = self.edges.keys().copied().collect();
+
+            for (e1, e2) in keys{
+                if constrained(e1, e2){
+                    continue;
+                }
+
+                let Some(list) = self.edges.get(&(e1, e2)) else{
+                    continue;
+                };
+
+                if list.len() != 2{
+                    continue;
+                }
+
+                let (t1, t2) = (list[0], list[1]);
+
+                // rotate t1 so it reads a b c with a b being the shared edge
+                let tri = self.triangles[t1];
+                let k = (0..3).find(|&k| edge_key(tri[k], tri[(k + 1) % 3]) == (e1, e2)).unwrap();
+                let (a, b, c) = (tri[k], tri[(k + 1) % 3], tri[(k + 2) % 3]);
+
+                let d = self.triangles[t2].iter().copied().find(|&v| v != a && v != b).unwrap();
+
+                let pa = self.points[a];
+                let pb = self.points[b];
+                let pc = self.points[c];
+                let pd = self.points[d];
+
+                if in_circle(pa, pb, pc, pd) <= EPSILON{
+                    continue;
+                }
+
+                // only flip if the quad is convex, otherwise the new edge would leave it
+                if orient(pd, pc, pa) <= EPSILON || orient(pc, pd, pb) <= EPSILON{
+                    continue;
+                }
+
+                self.set_triangle(t1, [a, d, c]);
+                self.set_triangle(t2, [d, b, c]);
+
+                flipped = true;
+            }
+        }
+    }
+}
+
+// points on a hex grid inside the outline, kept away from the outline itself
+fn steiner_points(
+    outline: &[Vec2],
+    spacing: f32,
+) -> Vec<Vec2>{
+    let mut min = Vec2::splat(f32::MAX);
+    let mut max = Vec2::splat(f32::MIN);
+
+    for pt in outline{
+        min = min.min(*pt);
+        max = max.max(*pt);
+    }
+
+    let row_height = spacing * 0.866;
+    let mut points = Vec::new();
+
+    let mut row = 0;
+    let mut y = min.y + row_height;
+
+    while y < max.y{
+        let offset = if row % 2 == 0 {0.0} else {spacing * 0.5};
+        let mut x = min.x + spacing * 0.5 + offset;
+
+        while x < max.x{
+            let pt = Vec2::new(x, y);
+
+            let clearance = (0..outline.len())
+                .map(|i| segment_dist(pt, outline[i], outline[(i + 1) % outline.len()]))
+                .fold(f32::MAX, f32::min);
+
+            if clearance > spacing * 0.6 && point_in_outline(pt, outline){
+                points.push(pt);
+            }
+
+            x += spacing;
+        }
+
+        y += row_height;
+        row += 1;
+    }
+
+    return points;
+}
+
+// the triangulation itself: points (the outline first, in the order given, then the
+// interior ones) and counter clockwise triangles
+pub fn triangulate_outline(
+    outline: &[Vec2],
+    interior_spacing: Option<f32>,
+) -> (Vec<Vec2>, Vec<[usize; 3]>){
+    let mut points = clean_outline(outline);
+
+    if points.len() < 3{
+        return (points, Vec::new());
+    }
+
+    let clockwise = signed_area(&points) < 0.0;
+
+    // ear clipping wants it counter clockwise, the indices are mapped back after
+    let ccw_order: Vec<usize> = if clockwise {(0..points.len()).rev().collect()} else {(0..points.len()).collect()};
+    let ccw_points: Vec<Vec2> = ccw_order.iter().map(|&i| points[i]).collect();
+
+    let triangles = ear_clip(&ccw_points).into_iter().map(|[a, b, c]| [ccw_order[a], ccw_order[b], ccw_order[c]]).collect();
+
+    let outline_len = points.len();
+
+    if let Some(spacing) = interior_spacing{
+        let interior = steiner_points(&points, spacing);
+        points.extend(interior);
+    }
+
+    let steiner: Vec<Vec2> = points.drain(outline_len..).collect();
+
+    let mut triangulation = Triangulation::new(points, triangles);
+
+    for pt in steiner{
+        triangulation.insert(pt);
+    }
+
+    let is_outline_edge = |i1: usize, i2: usize| {
+        i1 < outline_len && i2 < outline_len && ((i1 + 1) % outline_len == i2 || (i2 + 1) % outline_len == i1)
+    };
+
+    triangulation.make_delaunay(&is_outline_edge);
+
+    return (triangulation.points, triangulation.triangles);
+}
+
+// a whole soft body from an outline. The outline becomes the edges, wound clockwise,
+// every other triangle side a non edge connection with its exact length
+pub fn mesh_outline(
+    outline: &[Vec2],
+    interior_spacing: Option<f32>,
+) -> (Vec<SBNode>, Vec<SBConnection>){
+    let (points, triangles) = triangulate_outline(outline, interior_spacing);
+
+    return mesh_triangulation(&points, &triangles, clean_outline(outline).len());
+}
+
+// the soft body for a triangulation from triangulate_outline, the first
+// `outline_len` points being the outline
+pub fn mesh_triangulation(
+    points: &[Vec2],
+    triangles: &[[usize; 3]],
+    outline_len: usize,
+) -> (Vec<SBNode>, Vec<SBConnection>){
+    let clockwise = signed_area(&points[..outline_len.min(points.len())]) < 0.0;
+
+    let nodes: Vec<SBNode> = points.iter().map(|pt| SBNode::new(*pt)).collect();
+    let mut connections = Vec::new();
+
+    let mut seen: HashSet<(usize, usize)> = HashSet::new();
+
+    for triangle in triangles{
+        for k in 0..3{
+            let (i1, i2) = (triangle[k], triangle[(k + 1) % 3]);
+
+            if !seen.insert(edge_key(i1, i2)){
+                continue;
+            }
+
+            let resting_length = points[i1].distance(points[i2]);
+
+            let is_edge = i1 < outline_len && i2 < outline_len && ((i1 + 1) % outline_len == i2 || (i2 + 1) % outline_len == i1);
+
+            if !is_edge{
+                connections.push(SBConnection::new(i1, i2, false, resting_length));
+                continue;
+            }
+
+            // the outline edge i -> i + 1 goes clockwise when the outline does
+            let (from, to) = if (i1 + 1) % outline_len == i2 {(i1, i2)} else {(i2, i1)};
+
+            if clockwise{
+                connections.push(SBConnection::new(from, to, true, resting_length));
+            }
+            else{
+                connections.push(SBConnection::new(to, from, true, resting_length));
+            }
+        }
+    }
+
+    return (nodes, connections);
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn point(x: u32, y: u32) -> Vec2{
+        return Vec2::new(f32::from_bits(x), f32::from_bits(y));
+    }
+
+    // clockwise, like soft body outlines
+    fn square(corner: Vec2, size: f32) -> Vec<Vec2>{
+        return vec![corner, corner + Vec2::new(0.0, size), corner + Vec2::new(size, size), corner + Vec2::new(size, 0.0)];
+    }
+
+    #[test]
+    fn orient_gets_the_side_right_close_to_a_line(){
+        // plain f64 says these turn clockwise
+        let (a, b, c) = (point(0xcb092952, 0xcacdbdfb), point(0x4b057734, 0x4ac832ce), point(0x399236bd, 0x395b521c));
+
+        assert!(orient(a, b, c) > 0.0);
+        assert!(orient(b, a, c) < 0.0);
+        assert!(orient(b, c, a) > 0.0);
+    }
+
+    #[test]
+    fn orient_of_collinear_points_is_zero(){
+        // plain f64 doesnt get 0 for these
+        let (a, b, c) = (point(0x4aef05e0, 0x4ab34468), point(0x3f13267e, 0x3edcb9bd), point(0x3a0972d8, 0x39ce2c44));
+
+        assert_eq!(orient(a, b, c), 0.0);
+        assert_eq!(orient(c, a, b), 0.0);
+
+        assert_eq!(orient(Vec2::ZERO, Vec2::new(1.0, 2.0), Vec2::new(3.0, 6.0)), 0.0);
+        assert_eq!(orient(Vec2::new(-1.0, 5.0), Vec2::new(0.0, 5.0), Vec2::new(7.0, 5.0)), 0.0);
+    }
+
+    #[test]
+    fn point_on_a_shared_vertex_is_in_one_outline(){
+        let squares = [
+            square(Vec2::new(-1.0, -1.0), 1.0),
+            square(Vec2::new(0.0, -1.0), 1.0),
+            square(Vec2::new(-1.0, 0.0), 1.0),
+            square(Vec2::new(0.0, 0.0), 1.0),
+        ];
+
+        let inside = squares.iter().filter(|outline| point_in_outline(Vec2::ZERO, outline)).count();
+
+        assert_eq!(inside, 1);
+    }
+
+    #[test]
+    fn point_on_a_shared_horizontal_edge_is_in_one_outline(){
+        let (below, above) = (square(Vec2::new(0.0, -1.0), 1.0), square(Vec2::ZERO, 1.0));
+
+        for x in [0.25, 0.5, 0.75]{
+            let pt = Vec2::new(x, 0.0);
+
+            assert_ne!(point_in_outline(pt, &below), point_in_outline(pt, &above));
+        }
+    }
+
+    #[test]
+    fn concave_outline(){
+        // a U opening upwards, clockwise
+        let outline = [
+            Vec2::new(0.0, 0.0), Vec2::new(0.0, 3.0), Vec2::new(1.0, 3.0), Vec2::new(1.0, 1.0),
+            Vec2::new(2.0, 1.0), Vec2::new(2.0, 3.0), Vec2::new(3.0, 3.0), Vec2::new(3.0, 0.0),
+        ];
+
+        assert!(point_in_outline(Vec2::new(0.5, 2.5), &outline));
+        assert!(point_in_outline(Vec2::new(2.5, 2.5), &outline));
+        assert!(point_in_outline(Vec2::new(1.5, 0.5), &outline));
+        assert!(!point_in_outline(Vec2::new(1.5, 2.0), &outline));
+        assert!(!point_in_outline(Vec2::new(4.0, 2.0), &outline));
+
+        // the outline goes around once, clockwise
+        assert_eq!(winding_number(Vec2::new(0.5, 0.5), (0..outline.len()).map(|i| (outline[i], outline[(i + 1) % outline.len()]))), -1);
+    }
+
+    #[test]
+    fn resample_without_a_spacing_keeps_the_outline(){
+        let outline = square(Vec2::ZERO, 10.0);
+
+        assert_eq!(resample_outline(&outline, 2.5).len(), 16);
+
+        for spacing in [0.0, -1.0, f32::NAN]{
+            assert_eq!(resample_outline(&outline, spacing), outline);
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use bevy::{
+    input::InputSystem,
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use std::{fs, path::PathBuf};
+
+use crate::settings::*;
+use crate::soft_body::*;
+use crate::rigid::*;
+use crate::fluid::*;
+use crate::water::*;
+
+pub const RECORDING_VERSION: u32 = 3;
+pub const RECORDING_PATH: &str = "recording.ron";
+
+// F2 saves everything recorded since startup (or since the last replay started),
+// F3 clears the world and replays RECORDING_PATH.
+// `cargo run -- --replay <file>` replays a file straight away
+pub struct SBReplayPlugin;
+
+impl Plugin for SBReplayPlugin{
+    fn build(&self, app: &mut App){
+        let mut replay = SBReplay::default();
+
+        let args: Vec<String> = std::env::args().collect();
+
+        if let Some(index) = args.iter().position(|arg| arg == "--replay"){
+            replay.pending = Some(PathBuf::from(args.get(index + 1).map(|path| path.as_str()).unwrap_or(RECORDING_PATH)));
+        }
+
+        app.init_resource::<SBRecorder>()
+        .insert_resource(replay)
+        .configure_sets(Update, SBSet::Input.run_if(not_replaying))
+        .add_systems(PreUpdate, (replay_shortcuts, start_replay).chain().after(InputSystem))
+        .add_systems(Update, replay_inputs.after(SBSet::Input).before(SBSet::Apply))
+        .add_systems(Update, record_inputs.in_set(SBSet::Apply));
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct RecordedInput{
+    pub step: u64,
+    pub input: SBInput,
+}
+
+// a run starts from an empty world with `settings`, then `inputs` get applied
+// at the simulation step they were recorded at
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Recording{
+    pub version: u32,
+    pub settings: SBSettings,
+    pub inputs: Vec<RecordedInput>,
+}
+
+impl Recording{
+    fn new(settings: SBSettings) -> Self{
+        Self{version: RECORDING_VERSION, settings, inputs: Vec::new()}
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String>{
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(|err| err.to_string())?;
+
+        return fs::write(path, text).map_err(|err| err.to_string());
+    }
+
+    pub fn load(path: &str) -> Result<Self, String>{
+        let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let recording: Recording = ron::from_str(&text).map_err(|err| err.to_string())?;
+
+        if recording.version != RECORDING_VERSION{
+            return Err(format!("unsupported recording version {} (expected {})", recording.version, RECORDING_VERSION));
+        }
+
+        return Ok(recording);
+    }
+}
+
+#[derive(Resource)]
+pub struct SBRecorder{
+    pub recording: Recording,
+}
+
+impl Default for SBRecorder{
+    fn default() -> Self{
+        Self{recording: Recording::new(SBSettings::default())}
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct SBReplay{
+    pub inputs: Vec<RecordedInput>,
+    pub cursor: usize,
+    pub active: bool,
+    // replay that starts at the beginning of the next frame
+    pub pending: Option<PathBuf>,
+}
+
+pub fn not_replaying(
+    replay: Res<SBReplay>,
+) -> bool{
+    return !replay.active;
+}
+
+fn replay_shortcuts(
+    input: Res<ButtonInput<KeyCode>>,
+    recorder: Res<SBRecorder>,
+    mut replay: ResMut<SBReplay>,
+){
+    if input.just_pressed(KeyCode::F2){
+        match recorder.recording.save(RECORDING_PATH){
+            Ok(()) => info!("Saved {} inputs to {}", recorder.recording.inputs.len(), RECORDING_PATH),
+            Err(err) => error!("Could not save recording: {}", err),
+        }
+    }
+
+    if input.just_pressed(KeyCode::F3){
+        replay.pending = Some(PathBuf::from(RECORDING_PATH));
+    }
+}
+
+// runs in PreUpdate so the old bodies are gone before the first replayed input
+fn start_replay(
+    mut commands: Commands,
+    body_query: Query<Entity, Or<(With<SB>, With<RigidBody>, With<Fluid>, With<WaterRegion>)>>,
+    mut replay: ResMut<SBReplay>,
+    mut recorder: ResMut<SBRecorder>,
+    mut settings: ResMut<SBSettings>,
+    mut step: ResMut<SimStep>,
+){
+    let Some(path) = replay.pending.take() else{
+        return;
+    };
+
+    let recording = match Recording::load(&path.to_string_lossy()){
+        Ok(recording) => recording,
+        Err(err) => {
+            error!("Could not load recording {:?}: {}", path, err);
+            return;
+        }
+    };
+
+    for entity in &body_query{
+        commands.entity(entity).despawn_recursive();
+    }
+
+    *settings = recording.settings.clone();
+    *step = SimStep(0);
+
+    // keep recording while replaying so the replayed run can be saved again
+    recorder.recording = Recording::new(recording.settings.clone());
+
+    replay.inputs = recording.inputs;
+    replay.cursor = 0;
+    replay.active = true;
+
+    info!("Replaying {} inputs from {:?}", replay.inputs.len(), path);
+}
+
+fn replay_inputs(
+    mut replay: ResMut<SBReplay>,
+    step: Res<SimStep>,
+    mut input_writer: EventWriter<SBInput>,
+){
+    if !replay.active{
+        return;
+    }
+
+    while replay.cursor < replay.inputs.len() && replay.inputs[replay.cursor].step <= step.0{
+        let input = replay.inputs[replay.cursor].input.clone();
+
+        input_writer.send(input);
+        replay.cursor += 1;
+    }
+
+    if replay.cursor >= replay.inputs.len(){
+        replay.active = false;
+        info!("Replay finished at step {}", step.0);
+    }
+}
+
+fn record_inputs(
+    mut input_reader: EventReader<SBInput>,
+    step: Res<SimStep>,
+    mut recorder: ResMut<SBRecorder>,
+){
+    for input in input_reader.read(){
+        recorder.recording.inputs.push(RecordedInput{step: step.0, input: input.clone()});
+    }
+}
@@ -0,0 +1,874 @@
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+    },
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::*;
+use crate::soft_body::*;
+use crate::contact::*;
+use crate::meshing::*;
+
+// Rigid bodies move as a whole, by their center and angle. They get stepped in the
+// same substeps as the soft bodies, and their contacts (with soft body nodes and
+// edges, other rigid bodies and the container) are solved right after the soft body
+// ones the same way, the rigid side turning pushes and impulses into linear and
+// angular motion.
+// B drops a ball, C a crate and L a lever (a plank on a fixed fulcrum) at the mouse
+pub struct SBRigidPlugin;
+
+impl Plugin for SBRigidPlugin{
+    fn build(&self, app: &mut App){
+        app.add_systems(Update, spawn_rigid.in_set(SBSet::Input))
+        .add_systems(Update, update_rigid_draw.after(SBSet::Simulate));
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum RigidShape{
+    Circle{radius: f32},
+    // around the center of mass, wound clockwise like the soft body outlines
+    Polygon{points: Vec<Vec2>},
+}
+
+#[derive(Component, Clone, Debug, Serialize, Deserialize)]
+pub struct RigidBody{
+    pub shape: RigidShape,
+    pub pos: Vec2,
+    pub angle: f32,
+    pub vel: Vec2,
+    pub ang_vel: f32,
+    pub mass: f32,
+    pub inertia: f32,
+    // never moves, like the fulcrum of a lever
+    pub fixed: bool,
+}
+
+impl RigidBody{
+    pub fn circle(radius: f32, pos: Vec2, density: f32) -> Self{
+        let mass = PI * radius * radius * density;
+
+        return Self::new(RigidShape::Circle{radius}, pos, mass, mass * radius * radius / 2.0);
+    }
+
+    // `points` are relative to `pos`, the body ends up centered on their center of mass.
+    // None when they dont enclose anything, with no mass the body couldnt move
+    pub fn polygon(points: &[Vec2], pos: Vec2, density: f32) -> Option<Self>{
+        let mut points = clean_outline(points);
+
+        if signed_area(&points) > 0.0{
+            points.reverse();
+        }
+
+        let mut area = 0.0;
+        let mut centroid = Vec2::ZERO;
+
+        for i in 0..points.len(){
+            let (p1, p2) = (points[i], points[(i + 1) % points.len()]);
+            let cross = p1.perp_dot(p2);
+
+            area += cross / 2.0;
+            centroid += (p1 + p2) * cross / 6.0;
+        }
+
+        if area.abs() <= f32::EPSILON{
+            return None;
+        }
+
+        centroid /= area;
+
+        let points: Vec<Vec2> = points.iter().map(|pt| *pt - centroid).collect();
+
+        // second moment of area around the center of mass
+        let mut inertia = 0.0;
+
+        for i in 0..points.len(){
+            let (p1, p2) = (points[i], points[(i + 1) % points.len()]);
+
+            inertia += p1.perp_dot(p2) * (p1.dot(p1) + p1.dot(p2) + p2.dot(p2)) / 12.0;
+        }
+
+        return Some(Self::new(RigidShape::Polygon{points}, pos + centroid, area.abs() * density, inertia.abs() * density));
+    }
+
+    fn new(shape: RigidShape, pos: Vec2, mass: f32, inertia: f32) -> Self{
+        Self{shape, pos, angle: 0.0, vel: Vec2::ZERO, ang_vel: 0.0, mass, inertia, fixed: false}
+    }
+
+    pub fn fixed(mut self) -> Self{
+        self.fixed = true;
+        return self;
+    }
+
+    pub fn inverse_mass(&self) -> f32{
+        if self.fixed || self.mass <= 0.0{
+            return 0.0;
+        }
+
+        return 1.0 / self.mass;
+    }
+
+    pub fn inverse_inertia(&self) -> f32{
+        if self.fixed || self.inertia <= 0.0{
+            return 0.0;
+        }
+
+        return 1.0 / self.inertia;
+    }
+
+    // how hard it is to move the point at `pt` along `normal`
+    pub fn inverse_mass_at(&self, pt: Vec2, normal: Vec2) -> f32{
+        let arm = (pt - self.pos).perp_dot(normal);
+
+        return self.inverse_mass() + arm * arm * self.inverse_inertia();
+    }
+
+    pub fn to_world(&self, local: Vec2) -> Vec2{
+        return self.pos + Vec2::from_angle(self.angle).rotate(local);
+    }
+
+    pub fn to_local(&self, pt: Vec2) -> Vec2{
+        return Vec2::from_angle(-self.angle).rotate(pt - self.pos);
+    }
+
+    pub fn vel_at(&self, pt: Vec2) -> Vec2{
+        return self.vel + (pt - self.pos).perp() * self.ang_vel;
+    }
+
+    pub fn apply_impulse(&mut self, pt: Vec2, impulse: Vec2){
+        self.ang_vel += (pt - self.pos).perp_dot(impulse) * self.inverse_inertia();
+        self.vel += impulse * self.inverse_mass();
+    }
+
+    // the positional version of apply_impulse
+    pub fn push(&mut self, pt: Vec2, delta: Vec2){
+        self.angle += (pt - self.pos).perp_dot(delta) * self.inverse_inertia();
+        self.pos += delta * self.inverse_mass();
+    }
+
+    pub fn world_points(&self) -> Vec<Vec2>{
+        match &self.shape{
+            RigidShape::Circle{..} => Vec::new(),
+            RigidShape::Polygon{points} => points.iter().map(|pt| self.to_world(*pt)).collect(),
+        }
+    }
+
+    pub fn bounding_box(&self) -> BoundingBox{
+        match &self.shape{
+            RigidShape::Circle{radius} => BoundingBox{min_pos: self.pos - *radius, max_pos: self.pos + *radius},
+            RigidShape::Polygon{..} => {
+                let points = self.world_points();
+
+                BoundingBox{
+                    min_pos: points.iter().fold(Vec2::splat(f32::MAX), |min, pt| min.min(*pt)),
+                    max_pos: points.iter().fold(Vec2::splat(f32::MIN), |max, pt| max.max(*pt)),
+                }
+            }
+        }
+    }
+
+    // how far a circle of `radius` around pt reaches into the body, and the way out of it
+    pub fn penetration(&self, pt: Vec2, radius: f32) -> Option<(Vec2, f32)>{
+        match &self.shape{
+            RigidShape::Circle{radius: own_radius} => {
+                let offset = pt - self.pos;
+                let dist = offset.length();
+
+                if dist >= own_radius + radius{
+                    return None;
+                }
+
+                return Some((offset.try_normalize().unwrap_or(Vec2::Y), own_radius + radius - dist));
+            }
+            RigidShape::Polygon{points} => {
+                if points.len() < MIN_NODES{
+                    return None;
+                }
+
+                let local = self.to_local(pt);
+
+                let mut min_dist = f32::MAX;
+                let mut closest = Vec2::ZERO;
+                let mut edge_normal = Vec2::ZERO;
+
+                for i in 0..points.len(){
+                    let (p1, p2) = (points[i], points[(i + 1) % points.len()]);
+
+                    let t = ((local - p1).dot(p2 - p1) / (p2 - p1).length_squared()).clamp(0.0, 1.0);
+                    let edge_pt = p1 + (p2 - p1) * t;
+                    let dist = local.distance(edge_pt);
+
+                    if dist < min_dist{
+                        min_dist = dist;
+                        closest = edge_pt;
+                        // the outside is on the left
+                        edge_normal = (p2 - p1).normalize_or_zero().perp();
+                    }
+                }
+
+                let (normal, depth) = if point_in_outline(local, points){
+                    (edge_normal, min_dist + radius)
+                }
+                else if min_dist < radius{
+                    ((local - closest).try_normalize().unwrap_or(edge_normal), radius - min_dist)
+                }
+                else{
+                    return None;
+                };
+
+                return Some((Vec2::from_angle(self.angle).rotate(normal), depth));
+            }
+        }
+    }
+
+    // the points that can touch the container
+    fn extreme_points(&self) -> Vec<Vec2>{
+        match &self.shape{
+            RigidShape::Circle{radius} => vec![
+                self.pos - Vec2::X * *radius,
+                self.pos + Vec2::X * *radius,
+                self.pos - Vec2::Y * *radius,
+            ],
+            RigidShape::Polygon{..} => self.world_points(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+enum RigidOther{
+    Soft(Entity, ContactPoint),
+    // the point on the other rigid body, in its own space
+    Rigid(Entity, Vec2),
+    // where the wall is
+    Container(Vec2),
+}
+
+#[derive(Clone, Debug)]
+struct RigidContact{
+    body: Entity,
+    // the contact point on the rigid body, in its own space
+    local: Vec2,
+    other: RigidOther,
+    // the way the rigid body has to go to get out
+    normal: Vec2,
+    // out once (point - other point) . normal reaches this
+    target: f32,
+    separating_vel: f32,
+    friction: f32,
+    // normal impulse applied so far
+    impulse: f32,
+    // and along the contact, for the friction
+    tangent_impulse: f32,
+}
+
+impl RigidContact{
+    // `material` is the soft body's when the other side is one. Rigid bodies dont have a
+    // material of their own, so against them and the walls there is no friction
+    fn new(
+        body: Entity,
+        rigid: &RigidBody,
+        pt: Vec2,
+        other: RigidOther,
+        (other_pos, other_vel): (Vec2, Vec2),
+        (normal, depth): (Vec2, f32),
+        material: Option<&SBMaterial>,
+    ) -> Self{
+        let gap = (pt - other_pos).dot(normal);
+        let approach = (rigid.vel_at(pt) - other_vel).dot(normal);
+
+        let (restitution, friction) = material.map(|material| (material.restitution, material.friction)).unwrap_or((COLLISION_RESTITUTION, 0.0));
+
+        Self{
+            body,
+            local: rigid.to_local(pt),
+            other,
+            normal,
+            target: gap + depth,
+            separating_vel: if approach < 0.0 {-approach * restitution} else {0.0},
+            friction,
+            impulse: 0.0,
+            tangent_impulse: 0.0,
+        }
+    }
+}
+
+pub fn update_rigid_bodies(
+    rigid_query: &mut Query<(Entity, &mut RigidBody)>,
+    dt: f32,
+    gravity: Vec2,
+){
+    for (_, mut rigid) in rigid_query.iter_mut(){
+        if rigid.fixed{
+            continue;
+        }
+
+        rigid.vel -= gravity * dt * ITERATION_DELTA;
+
+        let (vel, ang_vel) = (rigid.vel, rigid.ang_vel);
+        rigid.pos += vel * dt * ITERATION_DELTA;
+        rigid.angle += ang_vel * dt * ITERATION_DELTA;
+    }
+}
+
+// finds everything touching a rigid body this substep and solves it
+pub fn update_rigid_collisions(
+    sb_query: &mut Query<(Entity, &mut SB)>,
+    rigid_query: &mut Query<(Entity, &mut RigidBody)>,
+){
+    let mut contacts = Vec::new();
+
+    for (rigid_entity, rigid) in rigid_query.iter(){
+        if !rigid.fixed{
+            container_contacts(rigid_entity, rigid, &mut contacts);
+        }
+
+        for (sb_entity, sb) in sb_query.iter(){
+            soft_rigid_contacts(sb_entity, sb, rigid_entity, rigid, &mut contacts);
+        }
+    }
+
+    for [(entity1, rigid1), (entity2, rigid2)] in rigid_query.iter_combinations(){
+        if rigid1.fixed && rigid2.fixed{
+            continue;
+        }
+
+        if let (RigidShape::Polygon{..}, RigidShape::Polygon{..}) = (&rigid1.shape, &rigid2.shape){
+            polygon_contacts(entity1, rigid1, entity2, rigid2, &mut contacts);
+            continue;
+        }
+
+        rigid_rigid_contacts(entity1, rigid1, entity2, rigid2, &mut contacts);
+        rigid_rigid_contacts(entity2, rigid2, entity1, rigid1, &mut contacts);
+    }
+
+    solve_rigid_contacts(sb_query, rigid_query, &mut contacts);
+}
+
+fn container_contacts(
+    entity: Entity,
+    rigid: &RigidBody,
+    contacts: &mut Vec<RigidContact>,
+){
+    for pt in rigid.extreme_points(){
+        let walls = [
+            (Vec2::Y, -HALF_DIM.y - pt.y),
+            (Vec2::X, -HALF_DIM.x - pt.x),
+            (-Vec2::X, pt.x - HALF_DIM.x),
+        ];
+
+        for (normal, depth) in walls{
+            if depth > 0.0{
+                contacts.push(RigidContact::new(entity, rigid, pt, RigidOther::Container(pt), (pt, Vec2::ZERO), (normal, depth), None));
+            }
+        }
+    }
+}
+
+fn soft_rigid_contacts(
+    sb_entity: Entity,
+    sb: &SB,
+    rigid_entity: Entity,
+    rigid: &RigidBody,
+    contacts: &mut Vec<RigidContact>,
+){
+    // chain nodes are round, they can touch without being inside
+    let bounding_box = rigid.bounding_box().grown(NODE_RADIUS);
+
+    if !bounding_box_collision(&sb.bounding_box, &bounding_box){
+        return;
+    }
+
+    // nodes inside the rigid body
+    for index in 0..sb.node_num{
+        let node_pos = sb.nodes[index].read_pos;
+
+        if !bounding_box.contains(node_pos){
+            continue;
+        }
+
+        let node_radius = if sb.chain_nodes.binary_search(&index).is_ok() {NODE_RADIUS} else {0.0};
+
+        let Some((normal, depth)) = rigid.penetration(node_pos, node_radius) else{
+            continue;
+        };
+
+        let point = [(index, 1.0), (index, 0.0)];
+        let pt = contact_pos(sb, &point);
+
+        contacts.push(RigidContact::new(rigid_entity, rigid, pt, RigidOther::Soft(sb_entity, point), (pt, contact_vel(sb, &point)), (-normal, depth), Some(&sb.material)));
+    }
+
+    match &rigid.shape{
+        // corners inside the soft body
+        RigidShape::Polygon{..} => {
+            for corner in rigid.world_points(){
+                if !sb.bounding_box.contains(corner) || !sb_point_intersection(corner, sb){
+                    continue;
+                }
+
+                let (edge_pt, dist, edge, t) = get_closest_edge(corner, rigid.pos, sb);
+                let connection = &sb.connections[edge];
+
+                // the program probably found a faulty intersection
+                if dist >= connection.resting_length / 2.0 || dist <= f32::EPSILON{
+                    continue;
+                }
+
+                let t = t.clamp(0.0, 1.0);
+                let point = [(connection.i1, 1.0 - t), (connection.i2, t)];
+
+                contacts.push(RigidContact::new(rigid_entity, rigid, corner, RigidOther::Soft(sb_entity, point), (contact_pos(sb, &point), contact_vel(sb, &point)), ((edge_pt - corner) / dist, dist), Some(&sb.material)));
+            }
+
+            // corners poking into a chain edge between two nodes outside of the polygon,
+            // like for circles either side of the edge can hit
+            let bounding_box = sb.bounding_box.grown(NODE_RADIUS);
+
+            for corner in rigid.world_points(){
+                if !bounding_box.contains(corner){
+                    continue;
+                }
+
+                for &edge in sb.chains.iter().flatten(){
+                    let connection = &sb.connections[edge];
+                    let (p1, p2) = (sb.nodes[connection.i1].read_pos, sb.nodes[connection.i2].read_pos);
+
+                    if p1.distance(corner) < NODE_RADIUS || p2.distance(corner) < NODE_RADIUS{
+                        continue;
+                    }
+
+                    let t = ((corner - p1).dot(p2 - p1) / (p2 - p1).length_squared()).clamp(0.0, 1.0);
+                    let offset = corner - (p1 + (p2 - p1) * t);
+                    let dist = offset.length();
+
+                    if dist >= NODE_RADIUS || dist <= f32::EPSILON{
+                        continue;
+                    }
+
+                    let normal = offset / dist;
+                    let point = [(connection.i1, 1.0 - t), (connection.i2, t)];
+
+                    contacts.push(RigidContact::new(rigid_entity, rigid, corner, RigidOther::Soft(sb_entity, point), (contact_pos(sb, &point) + normal * NODE_RADIUS, contact_vel(sb, &point)), (normal, NODE_RADIUS - dist), Some(&sb.material)));
+                }
+            }
+        }
+        // edges cutting into the circle between two nodes outside of it. Chain edges
+        // are as thick as their nodes and have no outside, either side can hit
+        RigidShape::Circle{radius} => {
+            let edges = sb.boundary.iter().flatten().map(|&edge| (edge, 0.0))
+                .chain(sb.chains.iter().flatten().map(|&edge| (edge, NODE_RADIUS)));
+
+            for (edge, thickness) in edges{
+                let connection = &sb.connections[edge];
+                let (p1, p2) = (sb.nodes[connection.i1].read_pos, sb.nodes[connection.i2].read_pos);
+                let reach = radius + thickness;
+
+                if p1.distance(rigid.pos) < reach || p2.distance(rigid.pos) < reach{
+                    continue;
+                }
+
+                let t = ((rigid.pos - p1).dot(p2 - p1) / (p2 - p1).length_squared()).clamp(0.0, 1.0);
+                let offset = rigid.pos - (p1 + (p2 - p1) * t);
+                let dist = offset.length();
+
+                // the outside of the edge has to face the circle
+                if dist >= reach || dist <= f32::EPSILON || (thickness == 0.0 && offset.dot((p2 - p1).perp()) <= 0.0){
+                    continue;
+                }
+
+                let normal = offset / dist;
+                let point = [(connection.i1, 1.0 - t), (connection.i2, t)];
+
+                contacts.push(RigidContact::new(rigid_entity, rigid, rigid.pos - normal * *radius, RigidOther::Soft(sb_entity, point), (contact_pos(sb, &point) + normal * thickness, contact_vel(sb, &point)), (normal, reach - dist), Some(&sb.material)));
+            }
+        }
+    }
+}
+
+// the contacts for rigid1 reaching into rigid2, corners for polygons and the whole circle
+// for circles. At least one of them is a circle
+fn rigid_rigid_contacts(
+    entity1: Entity,
+    rigid1: &RigidBody,
+    entity2: Entity,
+    rigid2: &RigidBody,
+    contacts: &mut Vec<RigidContact>,
+){
+    if !bounding_box_collision(&rigid1.bounding_box(), &rigid2.bounding_box()){
+        return;
+    }
+
+    let probes = match &rigid1.shape{
+        RigidShape::Circle{radius} => vec![(rigid1.pos, *radius)],
+        RigidShape::Polygon{..} => rigid1.world_points().into_iter().map(|pt| (pt, 0.0)).collect(),
+    };
+
+    for (center, radius) in probes{
+        let Some((normal, depth)) = rigid2.penetration(center, radius) else{
+            continue;
+        };
+
+        // the deepest point of rigid1
+        let pt = center - normal * radius;
+
+        contacts.push(RigidContact::new(entity1, rigid1, pt, RigidOther::Rigid(entity2, rigid2.to_local(pt)), (pt, rigid2.vel_at(pt)), (normal, depth), None));
+    }
+}
+
+// corners alone miss boxes sitting flush on each other, so polygons use the edge normal
+// they overlap the least along, and the edge of the other one facing it cut down to it
+fn polygon_contacts(
+    entity1: Entity,
+    rigid1: &RigidBody,
+    entity2: Entity,
+    rigid2: &RigidBody,
+    contacts: &mut Vec<RigidContact>,
+){
+    if !bounding_box_collision(&rigid1.bounding_box(), &rigid2.bounding_box()){
+        return;
+    }
+
+    let (points1, points2) = (rigid1.world_points(), rigid2.world_points());
+
+    let (edge1, separation1) = least_overlap(&points1, &points2);
+    let (edge2, separation2) = least_overlap(&points2, &points1);
+
+    if separation1 >= 0.0 || separation2 >= 0.0{
+        return;
+    }
+
+    if separation2 >= separation1{
+        face_contacts((entity1, rigid1, &points1), (entity2, rigid2, &points2), edge2, contacts);
+    }
+    else{
+        face_contacts((entity2, rigid2, &points2), (entity1, rigid1, &points1), edge1, contacts);
+    }
+}
+
+// the edge of `reference` the `other` points get the least far behind, and how far that is
+fn least_overlap(
+    reference: &[Vec2],
+    other: &[Vec2],
+) -> (usize, f32){
+    let mut best = (0, f32::MIN);
+
+    for i in 0..reference.len(){
+        let (p1, p2) = (reference[i], reference[(i + 1) % reference.len()]);
+        let normal = (p2 - p1).normalize_or_zero().perp();
+
+        let separation = other.iter().map(|pt| (*pt - p1).dot(normal)).fold(f32::MAX, f32::min);
+
+        if separation > best.1{
+            best = (i, separation);
+        }
+    }
+
+    return best;
+}
+
+// the `incident` body gets pushed out through `edge` of the `reference` one
+fn face_contacts(
+    (incident_entity, incident, incident_points): (Entity, &RigidBody, &[Vec2]),
+    (reference_entity, reference, reference_points): (Entity, &RigidBody, &[Vec2]),
+    edge: usize,
+    contacts: &mut Vec<RigidContact>,
+){
+    let (r1, r2) = (reference_points[edge], reference_points[(edge + 1) % reference_points.len()]);
+    let tangent = (r2 - r1).normalize_or_zero();
+    let normal = tangent.perp();
+
+    // the incident edge facing the reference edge the most
+    let edge_normal = |i: usize| (incident_points[(i + 1) % incident_points.len()] - incident_points[i]).normalize_or_zero().perp();
+
+    let Some(incident_edge) = (0..incident_points.len()).min_by(|&a, &b| edge_normal(a).dot(normal).total_cmp(&edge_normal(b).dot(normal))) else{
+        return;
+    };
+
+    let segment = (incident_points[incident_edge], incident_points[(incident_edge + 1) % incident_points.len()]);
+
+    // only the part of it alongside the reference edge
+    let Some(segment) = clip_segment(segment, tangent, r1.dot(tangent)) else{
+        return;
+    };
+
+    let Some((p1, p2)) = clip_segment(segment, -tangent, -r2.dot(tangent)) else{
+        return;
+    };
+
+    for pt in [p1, p2]{
+        let separation = (pt - r1).dot(normal);
+
+        if separation < 0.0{
+            contacts.push(RigidContact::new(incident_entity, incident, pt, RigidOther::Rigid(reference_entity, reference.to_local(pt)), (pt, reference.vel_at(pt)), (normal, -separation), None));
+        }
+    }
+}
+
+// cuts the segment down to where pt . dir >= offset, None when nothing is left
+fn clip_segment(
+    (mut p1, mut p2): (Vec2, Vec2),
+    dir: Vec2,
+    offset: f32,
+) -> Option<(Vec2, Vec2)>{
+    let (d1, d2) = (p1.dot(dir) - offset, p2.dot(dir) - offset);
+
+    if d1 < 0.0 && d2 < 0.0{
+        return None;
+    }
+
+    if d1 < 0.0{
+        p1 += (p2 - p1) * (d1 / (d1 - d2));
+    }
+    else if d2 < 0.0{
+        p2 = p1 + (p2 - p1) * (d1 / (d1 - d2));
+    }
+
+    return Some((p1, p2));
+}
+
+// one pass over a contact, moves the rigid body and returns the push and impulse
+// the other side takes the opposite of. Only soft bodies bring friction, and how hard
+// they are to move doesnt depend on the direction, so `other_inverse_mass` does for both
+fn solve_rigid_contact(
+    contact: &mut RigidContact,
+    rigid: &mut RigidBody,
+    other_pos: Vec2,
+    other_vel: Vec2,
+    other_inverse_mass: f32,
+) -> (Vec2, Vec2){
+    let pt = rigid.to_world(contact.local);
+    let inverse_mass = rigid.inverse_mass_at(pt, contact.normal) + other_inverse_mass;
+
+    if inverse_mass <= 0.0{
+        return (Vec2::ZERO, Vec2::ZERO);
+    }
+
+    let approach = (rigid.vel_at(pt) - other_vel).dot(contact.normal);
+
+    // the total impulse can only ever push, so it gets clamped instead of each step
+    let impulse = (contact.impulse + (contact.separating_vel - approach) / inverse_mass).max(0.0);
+    let impulse_delta = impulse - contact.impulse;
+    contact.impulse = impulse;
+
+    let gap = (pt - other_pos).dot(contact.normal);
+    let push = (contact.target - gap).max(0.0) / inverse_mass;
+
+    rigid.apply_impulse(pt, contact.normal * impulse_delta);
+    rigid.push(pt, contact.normal * push);
+
+    if contact.friction <= 0.0{
+        return (contact.normal * push, contact.normal * impulse_delta);
+    }
+
+    let tangent = contact.normal.perp();
+    let tangent_inverse_mass = rigid.inverse_mass_at(pt, tangent) + other_inverse_mass;
+
+    if tangent_inverse_mass <= 0.0{
+        return (contact.normal * push, contact.normal * impulse_delta);
+    }
+
+    let slide = (rigid.vel_at(pt) - other_vel).dot(tangent);
+
+    let limit = contact.friction * contact.impulse;
+    let tangent_impulse = (contact.tangent_impulse - slide / tangent_inverse_mass).clamp(-limit, limit);
+    let tangent_delta = tangent_impulse - contact.tangent_impulse;
+    contact.tangent_impulse = tangent_impulse;
+
+    rigid.apply_impulse(pt, tangent * tangent_delta);
+
+    return (contact.normal * push, contact.normal * impulse_delta + tangent * tangent_delta);
+}
+
+fn solve_rigid_contacts(
+    sb_query: &mut Query<(Entity, &mut SB)>,
+    rigid_query: &mut Query<(Entity, &mut RigidBody)>,
+    contacts: &mut [RigidContact],
+){
+    for _ in 0..CONTACT_ITERATIONS{
+        for contact in contacts.iter_mut(){
+            match contact.other{
+                RigidOther::Soft(entity, point) => {
+                    let (Ok((_, mut rigid)), Ok((_, mut sb))) = (rigid_query.get_mut(contact.body), sb_query.get_mut(entity)) else{
+                        continue;
+                    };
+
+                    let (push, impulse) = solve_rigid_contact(contact, &mut rigid, contact_pos(&sb, &point), contact_vel(&sb, &point), contact_inverse_mass(&sb, &point));
+
+                    apply_contact_point(&mut sb, &point, -push, -impulse);
+                }
+                RigidOther::Rigid(entity, local) => {
+                    let Ok([(_, mut rigid), (_, mut other)]) = rigid_query.get_many_mut([contact.body, entity]) else{
+                        continue;
+                    };
+
+                    let other_pt = other.to_world(local);
+
+                    let (push, impulse) = solve_rigid_contact(contact, &mut rigid, other_pt, other.vel_at(other_pt), other.inverse_mass_at(other_pt, contact.normal));
+
+                    other.apply_impulse(other_pt, -impulse);
+                    other.push(other_pt, -push);
+                }
+                RigidOther::Container(wall_pt) => {
+                    let Ok((_, mut rigid)) = rigid_query.get_mut(contact.body) else{
+                        continue;
+                    };
+
+                    solve_rigid_contact(contact, &mut rigid, wall_pt, Vec2::ZERO, 0.0);
+                }
+            }
+        }
+    }
+}
+
+fn rigid_mesh(
+    shape: &RigidShape,
+) -> Mesh{
+    match shape{
+        RigidShape::Circle{radius} => Mesh::from(Circle::new(*radius)),
+        RigidShape::Polygon{points} => {
+            let (points, triangles) = triangulate_outline(points, None);
+
+            let positions: Vec<[f32; 3]> = points.iter().map(|pt| [pt.x, pt.y, 0.0]).collect();
+            let indices: Vec<u32> = triangles.iter().flat_map(|triangle| triangle.iter().map(|&i| i as u32)).collect();
+
+            Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+            .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+            .with_inserted_indices(Indices::U32(indices))
+        }
+    }
+}
+
+pub fn spawn_rigid_entity(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    rigid: RigidBody,
+) -> Entity{
+    let color = if rigid.fixed {Color::srgb(0.5, 0.5, 0.5)} else {Color::srgb(0.8, 0.6, 0.3)};
+
+    return commands.spawn((
+        MaterialMesh2dBundle{
+            mesh: Mesh2dHandle(meshes.add(rigid_mesh(&rigid.shape))),
+            material: materials.add(color),
+            transform: Transform{
+                translation: rigid.pos.extend(0.0),
+                rotation: Quat::from_rotation_z(rigid.angle),
+                ..default()
+            },
+            ..default()
+        },
+        rigid,
+        Name::new("Rigid Body"),
+    )).id();
+}
+
+fn box_points(
+    size: Vec2,
+) -> Vec<Vec2>{
+    let half = size / 2.0;
+
+    return vec![Vec2::new(-half.x, -half.y), Vec2::new(-half.x, half.y), Vec2::new(half.x, half.y), Vec2::new(half.x, -half.y)];
+}
+
+fn spawn_rigid(
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    input: Res<ButtonInput<KeyCode>>,
+    mut input_writer: EventWriter<SBInput>,
+){
+    let Some(mouse_position) = q_windows.single().cursor_position() else{
+        return;
+    };
+
+    let mut pos = mouse_position - HALF_DIM;
+    pos.y = -pos.y;
+
+    let mut bodies = Vec::new();
+
+    if input.just_pressed(KeyCode::KeyB){
+        bodies.push(RigidBody::circle(BALL_RADIUS, pos, RIGID_DENSITY));
+    }
+
+    if input.just_pressed(KeyCode::KeyC){
+        bodies.extend(RigidBody::polygon(&box_points(Vec2::splat(CRATE_SIZE)), pos, RIGID_DENSITY));
+    }
+
+    if input.just_pressed(KeyCode::KeyL){
+        let fulcrum = [Vec2::new(-20.0, -25.0), Vec2::new(0.0, 0.0), Vec2::new(20.0, -25.0)];
+        let plank = box_points(Vec2::new(LEVER_LENGTH, 10.0));
+
+        bodies.extend(RigidBody::polygon(&fulcrum, pos, RIGID_DENSITY).map(RigidBody::fixed));
+        bodies.extend(RigidBody::polygon(&plank, pos + Vec2::Y * 5.0, RIGID_DENSITY));
+    }
+
+    for body in bodies{
+        input_writer.send(SBInput::SpawnRigid{body: Box::new(body)});
+    }
+}
+
+fn update_rigid_draw(
+    mut rigid_query: Query<(&RigidBody, &mut Transform)>,
+){
+    for (rigid, mut transform) in &mut rigid_query{
+        transform.translation = rigid.pos.extend(0.0);
+        transform.rotation = Quat::from_rotation_z(rigid.angle);
+    }
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    // a rope along the x axis, nodes 40 apart
+    fn rope() -> SB{
+        let nodes: Vec<SBNode> = (0..3).map(|i| SBNode::new(Vec2::new(i as f32 * 40.0 - 40.0, 0.0))).collect();
+        let connections = vec![SBConnection::new(0, 1, true, 40.0), SBConnection::new(1, 2, true, 40.0)];
+        let base_skeleton: Vec<Vec2> = nodes.iter().map(|node| node.read_pos).collect();
+
+        return SB::from_parts(&nodes, &connections, &base_skeleton, 0.0);
+    }
+
+    // corners 10 from `pos`, straight above, below and to the sides of it
+    fn diamond(pos: Vec2) -> RigidBody{
+        let points = [Vec2::new(0.0, -10.0), Vec2::new(-10.0, 0.0), Vec2::new(0.0, 10.0), Vec2::new(10.0, 0.0)];
+
+        return RigidBody::polygon(&points, pos, RIGID_DENSITY).unwrap();
+    }
+
+    #[test]
+    fn polygon_corner_hits_a_chain_edge(){
+        let sb = rope();
+
+        for side in [1.0, -1.0]{
+            // a corner 2 off the rope, between two of its nodes
+            let rigid = diamond(Vec2::new(20.0, 12.0 * side));
+            let mut contacts = Vec::new();
+
+            soft_rigid_contacts(Entity::PLACEHOLDER, &sb, Entity::PLACEHOLDER, &rigid, &mut contacts);
+
+            assert_eq!(contacts.len(), 1);
+            assert!(contacts[0].normal.abs_diff_eq(Vec2::Y * side, 1e-4));
+        }
+    }
+
+    #[test]
+    fn flat_polygon_has_no_body(){
+        let line = [Vec2::new(-10.0, 0.0), Vec2::new(0.0, 0.0), Vec2::new(10.0, 0.0)];
+
+        assert!(RigidBody::polygon(&line, Vec2::ZERO, RIGID_DENSITY).is_none());
+        assert!(RigidBody::polygon(&[], Vec2::ZERO, RIGID_DENSITY).is_none());
+    }
+
+    #[test]
+    fn polygon_corner_clear_of_a_chain_edge(){
+        let mut contacts = Vec::new();
+
+        soft_rigid_contacts(Entity::PLACEHOLDER, &rope(), Entity::PLACEHOLDER, &diamond(Vec2::new(20.0, NODE_RADIUS + 11.0)), &mut contacts);
+
+        assert!(contacts.is_empty());
+    }
+}
@@ -1,4 +1,5 @@
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::f32::consts::PI as OtherPI;
 
 // pub const SCREENSIZE: Vec2 = Vec2::new(1280.0, 680.0);
@@ -7,16 +8,95 @@ pub const SCREENSIZE: Vec2 = Vec2::new(500.0, 500.0);
 pub const HALF_DIM: Vec2 = Vec2::new(SCREENSIZE.x / 2.0, SCREENSIZE.y / 2.0);
 pub const NODE_RADIUS: f32 = 5.0;
 pub const DEFAULT_RESTING_LENGTH: f32 = 50.0;
+// the smallest closed outline
+pub const MIN_NODES: usize = 3;
 
 // yes these are default values. Womp Womp
 pub const DEFAULT_STIFFNESS: f32 = 30.0;
 pub const DEFAULT_DAMPENING: f32 = 4.0;
 pub const SKELETON_STIFFNESS: f32 = 10.0;
+// nodes closer than this to the center of the rest shape count as on it
+pub const HUB_TOLERANCE: f32 = 0.01;
+
+pub const DEFAULT_NODE_MASS: f32 = 1.0;
+// how much of the speed two bodies hit each other with they bounce back with.
+// Soft bodies take theirs from the material, this is the default
+pub const COLLISION_RESTITUTION: f32 = 0.1;
+// how far a spring can be stretched or squashed, as a share of its resting
+// length, before a plastic material starts to keep the new shape
+pub const PLASTIC_YIELD: f32 = 0.15;
+// how fast a body grows or shrinks toward the size it was inflated to, as a share
+// of its size per unit of time. Slow enough for it to push things out of the way
+pub const INFLATE_RATE: f32 = 0.1;
+// how much bigger one notch of the scroll wheel makes a body
+pub const INFLATE_SCROLL_STEP: f32 = 1.1;
+// how much bigger a body gets every second = or - is held
+pub const INFLATE_HOLD_RATE: f32 = 1.8;
+// how far a body can be inflated or deflated from the size it was spawned at
+pub const MIN_INFLATION: f32 = 0.25;
+pub const MAX_INFLATION: f32 = 4.0;
+// passes over all the contacts every substep
+pub const CONTACT_ITERATIONS: usize = 4;
+// how much of last substep's contact impulse gets applied up front
+pub const WARM_START_FACTOR: f32 = 0.8;
+
+// rigid bodies weigh about as much as a soft body of the same size
+pub const RIGID_DENSITY: f32 = DEFAULT_NODE_MASS / (DEFAULT_RESTING_LENGTH * DEFAULT_RESTING_LENGTH);
+pub const BALL_RADIUS: f32 = 25.0;
+pub const CRATE_SIZE: f32 = 60.0;
+pub const LEVER_LENGTH: f32 = 240.0;
+
+// soft bodies have about a node every half resting length, the fluid
+// is twice as heavy as that so they float in it
+pub const FLUID_DENSITY: f32 = 2.0 * DEFAULT_NODE_MASS / (DEFAULT_RESTING_LENGTH * DEFAULT_RESTING_LENGTH / 4.0);
+pub const FLUID_SPACING: f32 = 8.0;
+pub const FLUID_PARTICLE_MASS: f32 = FLUID_DENSITY * FLUID_SPACING * FLUID_SPACING;
+// how far particles feel each other
+pub const FLUID_SMOOTHING: f32 = FLUID_SPACING * 2.0;
+// how hard the fluid pushes back when squeezed, the speed of sound squared
+pub const FLUID_STIFFNESS: f32 = 20000.0;
+pub const FLUID_VISCOSITY: f32 = 80.0;
+// the pressure and viscosity barely change between substeps, so they
+// only get worked out every this many
+pub const FLUID_FORCE_INTERVAL: i32 = 10;
+// particles per side of a poured block
+pub const FLUID_BLOCK: usize = 10;
+
+// water regions weigh the same as the fluid
+pub const WATER_DENSITY: f32 = FLUID_DENSITY;
+pub const WATER_DRAG: f32 = 0.5;
+pub const WATER_POOL_SIZE: Vec2 = Vec2::new(200.0, 120.0);
 
 pub const GRAVITY: Vec2 = Vec2::new(0.0, 9.87);
 
+// what I turns the pull between bodies up to
+pub const BODY_GRAVITY: f32 = 5000.0;
+// the push between two bodies with a charge of 1, pulls for opposite charges
+pub const CHARGE_STRENGTH: f32 = 500000.0;
+// bodies further apart than this dont feel each other, the forces fade out over the second half
+pub const FIELD_CUTOFF: f32 = 500.0;
+// keeps the forces finite when two centers get close
+pub const FIELD_SOFTENING: f32 = 20.0;
+
 pub const ITERATION_COUNT : i32 = 100;
 pub const ITERATION_DELTA : f32 = 1.0 / (ITERATION_COUNT as f32);
 
 pub const PI :f32 = OtherPI;
 pub const TAU :f32 = PI * 2.0;
+
+// the settings that can be changed while the app is running.
+// they go through SBInput so recordings pick them up
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SBSettings{
+    pub gravity: Vec2,
+    pub paused: bool,
+    // how hard bodies pull on each other by their mass, 0 is off
+    #[serde(default)]
+    pub body_gravity: f32,
+}
+
+impl Default for SBSettings{
+    fn default() -> Self{
+        Self{gravity: GRAVITY, paused: false, body_gravity: 0.0}
+    }
+}
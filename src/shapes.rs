@@ -0,0 +1,277 @@
+use bevy::prelude::*;
+
+use crate::settings::*;
+use crate::soft_body::*;
+use crate::definition::*;
+
+// Generators for common shapes. Everything is centered on the origin,
+// resting lengths are measured from the node positions and the outlines
+// are wound clockwise like get_closest_edge expects. Chains and strips are
+// open, their edges dont close up and they collide by distance instead.
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Bracing{
+    // only the grid lines, the skeleton is the only thing stopping shear
+    None,
+    // one diagonal per cell, alternating so the lattice doesnt lean one way
+    Single,
+    // both diagonals in every cell
+    Cross,
+}
+
+fn connect(
+    nodes: &Vec<SBNode>,
+    i1: usize,
+    i2: usize,
+    is_edge: bool,
+) -> SBConnection{
+    let resting_length = nodes[i1].read_pos.distance(nodes[i2].read_pos);
+
+    return SBConnection::new(i1, i2, is_edge, resting_length);
+}
+
+// `sides` nodes on a circle, with a hub node in the middle when `spokes` is set
+pub fn regular_polygon(
+    radius: f32,
+    sides: usize,
+    spokes: bool,
+) -> (Vec<SBNode>, Vec<SBConnection>){
+    let sides = sides.max(3);
+
+    let mut nodes = Vec::new();
+    let mut connections = Vec::new();
+
+    for i in 0..sides{
+        // going clockwise
+        let angle = -TAU * (i as f32) / (sides as f32);
+
+        nodes.push(SBNode::new(Vec2::from_angle(angle) * radius));
+    }
+
+    for i in 0..sides{
+        connections.push(connect(&nodes, i, (i + 1) % sides, true));
+    }
+
+    if spokes{
+        let hub = nodes.len();
+        nodes.push(SBNode::new(Vec2::ZERO));
+
+        for i in 0..sides{
+            connections.push(connect(&nodes, hub, i, false));
+        }
+    }
+    else{
+        // without a hub, brace every node to the one two steps away.
+        // a square would get both of its diagonals twice
+        let brace_count = if sides == 4 {2} else if sides > 4 {sides} else {0};
+
+        for i in 0..brace_count{
+            connections.push(connect(&nodes, i, (i + 2) % sides, false));
+        }
+    }
+
+    return (nodes, connections);
+}
+
+// a polygon fine enough to pass as a circle, `resolution` nodes on the rim
+pub fn circle(
+    radius: f32,
+    resolution: usize,
+) -> (Vec<SBNode>, Vec<SBConnection>){
+    return regular_polygon(radius, resolution, true);
+}
+
+// `columns` x `rows` cells, each `width / columns` by `height / rows`
+pub fn lattice(
+    width: f32,
+    height: f32,
+    columns: usize,
+    rows: usize,
+    bracing: Bracing,
+) -> (Vec<SBNode>, Vec<SBConnection>){
+    let columns = columns.max(1);
+    let rows = rows.max(1);
+
+    let cell = Vec2::new(width / (columns as f32), height / (rows as f32));
+    let top_left = Vec2::new(-width / 2.0, height / 2.0);
+
+    let index = |column: usize, row: usize| row * (columns + 1) + column;
+
+    let mut nodes = Vec::new();
+    let mut connections = Vec::new();
+
+    for row in 0..=rows{
+        for column in 0..=columns{
+            nodes.push(SBNode::new(top_left + Vec2::new(column as f32 * cell.x, -(row as f32) * cell.y)));
+        }
+    }
+
+    // outline, clockwise: along the top, down the right, back along the bottom, up the left
+    for column in 0..columns{
+        connections.push(connect(&nodes, index(column, 0), index(column + 1, 0), true));
+    }
+    for row in 0..rows{
+        connections.push(connect(&nodes, index(columns, row), index(columns, row + 1), true));
+    }
+    for column in (0..columns).rev(){
+        connections.push(connect(&nodes, index(column + 1, rows), index(column, rows), true));
+    }
+    for row in (0..rows).rev(){
+        connections.push(connect(&nodes, index(0, row + 1), index(0, row), true));
+    }
+
+    // inside grid lines
+    for row in 1..rows{
+        for column in 0..columns{
+            connections.push(connect(&nodes, index(column, row), index(column + 1, row), false));
+        }
+    }
+    for column in 1..columns{
+        for row in 0..rows{
+            connections.push(connect(&nodes, index(column, row), index(column, row + 1), false));
+        }
+    }
+
+    for row in 0..rows{
+        for column in 0..columns{
+            let tl = index(column, row);
+            let tr = index(column + 1, row);
+            let bl = index(column, row + 1);
+            let br = index(column + 1, row + 1);
+
+            match bracing{
+                Bracing::None => {}
+                Bracing::Single => {
+                    if (row + column) % 2 == 0{
+                        connections.push(connect(&nodes, tl, br, false));
+                    }
+                    else{
+                        connections.push(connect(&nodes, tr, bl, false));
+                    }
+                }
+                Bracing::Cross => {
+                    connections.push(connect(&nodes, tl, br, false));
+                    connections.push(connect(&nodes, tr, bl, false));
+                }
+            }
+        }
+    }
+
+    return (nodes, connections);
+}
+
+// two circles of `segments` nodes. The inner one is wound the other way round
+// since its outside is the hole
+pub fn ring(
+    outer_radius: f32,
+    inner_radius: f32,
+    segments: usize,
+) -> (Vec<SBNode>, Vec<SBConnection>){
+    let segments = segments.max(3);
+
+    let mut nodes = Vec::new();
+    let mut connections = Vec::new();
+
+    for i in 0..segments{
+        let angle = -TAU * (i as f32) / (segments as f32);
+
+        nodes.push(SBNode::new(Vec2::from_angle(angle) * outer_radius));
+    }
+
+    for i in 0..segments{
+        let angle = -TAU * (i as f32) / (segments as f32);
+
+        nodes.push(SBNode::new(Vec2::from_angle(angle) * inner_radius));
+    }
+
+    for i in 0..segments{
+        let next = (i + 1) % segments;
+
+        connections.push(connect(&nodes, i, next, true));
+        connections.push(connect(&nodes, segments + next, segments + i, true));
+
+        // spoke and diagonal across every segment
+        connections.push(connect(&nodes, i, segments + i, false));
+        connections.push(connect(&nodes, i, segments + next, false));
+    }
+
+    return (nodes, connections);
+}
+
+// a strip `segments` cells long, closed so it collides like any other body
+pub fn rope(
+    length: f32,
+    thickness: f32,
+    segments: usize,
+) -> (Vec<SBNode>, Vec<SBConnection>){
+    return lattice(length, thickness, segments, 1, Bracing::Cross);
+}
+
+// an open line of `segments` links going right, every node also tied to the one two
+// along so it bends a little stiffly instead of folding up. Needs an open body
+pub fn chain(
+    length: f32,
+    segments: usize,
+) -> (Vec<SBNode>, Vec<SBConnection>){
+    let segments = segments.max(2);
+
+    let mut nodes = Vec::new();
+    let mut connections = Vec::new();
+
+    for i in 0..=segments{
+        nodes.push(SBNode::new(Vec2::new(length * (i as f32 / segments as f32 - 0.5), 0.0)));
+    }
+
+    for i in 0..segments{
+        connections.push(connect(&nodes, i, i + 1, true));
+    }
+
+    for i in 0..segments - 1{
+        connections.push(connect(&nodes, i, i + 2, false));
+    }
+
+    return (nodes, connections);
+}
+
+// a braced strip like rope, but only the long sides are edges so it collides as two
+// open lines, like a strip of cloth. Needs an open body
+pub fn strip(
+    length: f32,
+    width: f32,
+    segments: usize,
+) -> (Vec<SBNode>, Vec<SBConnection>){
+    let (nodes, mut connections) = lattice(length, width, segments, 1, Bracing::Cross);
+
+    for connection in &mut connections{
+        if nodes[connection.i1].read_pos.x == nodes[connection.i2].read_pos.x{
+            connection.is_edge = false;
+        }
+    }
+
+    return (nodes, connections);
+}
+
+// open shapes hang from their springs alone, the skeleton would keep them straight
+fn open_shape(
+    (nodes, connections): (Vec<SBNode>, Vec<SBConnection>),
+) -> SBDefinition{
+    let mut definition = SBDefinition::from_parts(&nodes, &connections);
+    definition.open = true;
+    definition.material.skeleton_stiffness = 0.0;
+
+    return definition;
+}
+
+// what the shape keys after the files spawn
+pub fn default_generated_shapes() -> Vec<(String, SBDefinition)>{
+    let closed = |(nodes, connections): (Vec<SBNode>, Vec<SBConnection>)| SBDefinition::from_parts(&nodes, &connections);
+
+    return vec![
+        ("generated/circle".to_string(), closed(circle(DEFAULT_RESTING_LENGTH, 12))),
+        ("generated/lattice".to_string(), closed(lattice(DEFAULT_RESTING_LENGTH * 3.0, DEFAULT_RESTING_LENGTH * 2.0, 3, 2, Bracing::Single))),
+        ("generated/ring".to_string(), closed(ring(DEFAULT_RESTING_LENGTH * 1.5, DEFAULT_RESTING_LENGTH, 12))),
+        ("generated/rope".to_string(), closed(rope(DEFAULT_RESTING_LENGTH * 6.0, DEFAULT_RESTING_LENGTH * 0.4, 12))),
+        ("generated/chain".to_string(), open_shape(chain(DEFAULT_RESTING_LENGTH * 6.0, 16))),
+        ("generated/strip".to_string(), open_shape(strip(DEFAULT_RESTING_LENGTH * 6.0, DEFAULT_RESTING_LENGTH * 0.6, 12))),
+    ];
+}
@@ -0,0 +1,496 @@
+use bevy::{
+    prelude::*,
+    utils::HashMap,
+};
+use bevy::window::PrimaryWindow;
+
+use crate::settings::*;
+use crate::soft_body::*;
+use crate::sprite::*;
+use crate::meshing::*;
+
+// X switches the left mouse button between dragging nodes and slicing. A slice is the
+// line from where the button went down to where it came up, and every connection it
+// crosses goes. Closed bodies get a new outline around what is left, and every piece
+// that no longer hangs together with the rest becomes a body of its own. A strip of outline
+// with nothing inside it is left as an open chain, bits smaller than that are dropped
+pub struct SBSlicePlugin;
+
+impl Plugin for SBSlicePlugin{
+    fn build(&self, app: &mut App){
+        app.add_systems(Update, (switch_tool, slice_line).chain().in_set(SBSet::Input));
+    }
+}
+
+// part of a sliced body, `nodes` are the indices its nodes had in the old one
+pub struct SBPiece{
+    pub sb: SB,
+    pub nodes: Vec<usize>,
+}
+
+// the pieces `sb` falls into when cut from `from` to `to`, None when the line misses it
+pub fn slice_sb(
+    sb: &SB,
+    from: Vec2,
+    to: Vec2,
+) -> Option<Vec<SBPiece>>{
+    let cut: Vec<bool> = sb.connections.iter().map(|connection| {
+        segment_intersection(from, to, sb.nodes[connection.i1].read_pos, sb.nodes[connection.i2].read_pos).is_some()
+    }).collect();
+
+    if !cut.contains(&true){
+        return None;
+    }
+
+    // open bodies keep their edges as they are, they dont need to close up
+    let open = !sb.chains.is_empty();
+
+    let mut neighbours: Vec<Vec<usize>> = vec![Vec::new(); sb.node_num];
+
+    for (index, connection) in sb.connections.iter().enumerate(){
+        if !cut[index]{
+            neighbours[connection.i1].push(connection.i2);
+            neighbours[connection.i2].push(connection.i1);
+        }
+    }
+
+    let mut pieces = Vec::new();
+
+    for nodes in connected_parts(&(0..sb.node_num).collect::<Vec<usize>>(), &neighbours){
+        if nodes.len() < MIN_NODES{
+            continue;
+        }
+
+        if open{
+            match build_piece(sb, &nodes, &cut, PieceKind::Open){
+                Ok(piece) => pieces.push(piece),
+                Err(_) => warn!("Dropped a slice that couldnt be made into a Soft Body"),
+            }
+        }
+        else{
+            close_pieces(sb, nodes, &cut, &neighbours, &mut pieces);
+        }
+    }
+
+    // a node the pieces got split at is in all of them, they share its mass
+    let mut count = vec![0; sb.node_num];
+
+    for piece in &pieces{
+        for &old in &piece.nodes{
+            count[old] += 1;
+        }
+    }
+
+    for piece in &mut pieces{
+        for (node, &old) in piece.sb.nodes.iter_mut().zip(&piece.nodes){
+            node.mass /= count[old] as f32;
+        }
+    }
+
+    return Some(pieces);
+}
+
+// the outline would have to go out and back along anything hanging off the piece by a
+// single connection, so that gets cut off. When that leaves nothing, like for a strip of
+// the outline on its own, the piece becomes a chain instead. Pieces that only hang together
+// by a single node, which the outline would have to go through twice, get split there
+fn close_pieces(
+    sb: &SB,
+    nodes: Vec<usize>,
+    cut: &[bool],
+    neighbours: &[Vec<usize>],
+    pieces: &mut Vec<SBPiece>,
+){
+    let mut parts = vec![nodes];
+
+    while let Some(nodes) = parts.pop(){
+        let core = closed_core(&nodes, neighbours);
+
+        let piece = if core.len() >= MIN_NODES{
+            build_piece(sb, &core, cut, PieceKind::Closed)
+        }
+        else{
+            build_piece(sb, &nodes, cut, PieceKind::Chain)
+        };
+
+        match piece{
+            Ok(piece) => pieces.push(piece),
+            Err(Some(pinch)) => {
+                let rest: Vec<usize> = core.iter().copied().filter(|&node| node != pinch).collect();
+                let split = connected_parts(&rest, neighbours);
+
+                if split.len() < 2{
+                    warn!("Dropped a slice that couldnt be made into a Soft Body");
+                    continue;
+                }
+
+                for mut part in split{
+                    part.push(pinch);
+                    part.sort_unstable();
+                    parts.push(part);
+                }
+            }
+            Err(None) => warn!("Dropped a slice that couldnt be made into a Soft Body"),
+        }
+    }
+}
+
+// the groups of `nodes` that hang together, going only through `nodes`
+fn connected_parts(
+    nodes: &[usize],
+    neighbours: &[Vec<usize>],
+) -> Vec<Vec<usize>>{
+    let mut visited: HashMap<usize, bool> = nodes.iter().map(|&node| (node, false)).collect();
+    let mut parts = Vec::new();
+
+    for &start in nodes{
+        if visited[&start]{
+            continue;
+        }
+
+        let mut part = Vec::new();
+        let mut stack = vec![start];
+        visited.insert(start, true);
+
+        while let Some(node) = stack.pop(){
+            part.push(node);
+
+            for other in &neighbours[node]{
+                if visited.get(other) == Some(&false){
+                    visited.insert(*other, true);
+                    stack.push(*other);
+                }
+            }
+        }
+
+        part.sort_unstable();
+        parts.push(part);
+    }
+
+    return parts;
+}
+
+// what is left of `nodes` after taking off every node with less than two neighbours among them, over and over
+fn closed_core(
+    nodes: &[usize],
+    neighbours: &[Vec<usize>],
+) -> Vec<usize>{
+    let mut kept: HashMap<usize, bool> = nodes.iter().map(|&node| (node, true)).collect();
+    let mut changed = true;
+
+    while changed{
+        changed = false;
+
+        for &node in nodes{
+            if kept[&node] && neighbours[node].iter().filter(|other| kept.get(*other) == Some(&true)).count() < 2{
+                kept.insert(node, false);
+                changed = true;
+            }
+        }
+    }
+
+    return nodes.iter().copied().filter(|node| kept[node]).collect();
+}
+
+// how the edges of a piece are found
+#[derive(Clone, Copy, PartialEq)]
+enum PieceKind{
+    // walked around again, the old outline got cut
+    Closed,
+    // kept from the old body, which was open already
+    Open,
+    // every connection, for what is left of a closed body that cant close up
+    Chain,
+}
+
+// Err has the node the outline went through twice, if that is what went wrong
+fn build_piece(
+    sb: &SB,
+    nodes: &[usize],
+    cut: &[bool],
+    kind: PieceKind,
+) -> Result<SBPiece, Option<usize>>{
+    let new_index: HashMap<usize, usize> = nodes.iter().enumerate().map(|(new, &old)| (old, new)).collect();
+    let new_nodes: Vec<SBNode> = nodes.iter().map(|&old| sb.nodes[old].clone()).collect();
+
+    let mut connections = Vec::new();
+    let mut new_connection = HashMap::new();
+
+    for (index, connection) in sb.connections.iter().enumerate(){
+        let (Some(&i1), Some(&i2)) = (new_index.get(&connection.i1), new_index.get(&connection.i2)) else{
+            continue;
+        };
+
+        if cut[index]{
+            continue;
+        }
+
+        let mut connection = connection.clone();
+        connection.i1 = i1;
+        connection.i2 = i2;
+        connection.is_edge = match kind{
+            PieceKind::Closed => false,
+            PieceKind::Open => connection.is_edge,
+            PieceKind::Chain => true,
+        };
+
+        new_connection.insert(index, connections.len());
+        connections.push(connection);
+    }
+
+    if kind == PieceKind::Closed{
+        let positions: Vec<Vec2> = new_nodes.iter().map(|node| node.read_pos).collect();
+
+        let outline = outer_loop(&positions, &connections).map_err(|pinch| pinch.map(|pinch| nodes[pinch]))?;
+        let mut on_outline = vec![false; positions.len()];
+
+        for &edge in &outline{
+            connections[edge].is_edge = true;
+            on_outline[connections[edge].i1] = true;
+            on_outline[connections[edge].i2] = true;
+        }
+
+        // holes the cut went past stay holes
+        for edge_loop in &sb.boundary{
+            let hole: Vec<Vec2> = edge_loop.iter().map(|&edge| sb.nodes[sb.connections[edge].i1].read_pos).collect();
+
+            // outer loops are clockwise
+            if signed_area(&hole) <= 0.0{
+                continue;
+            }
+
+            let Some(edges) = edge_loop.iter().map(|edge| new_connection.get(edge).copied()).collect::<Option<Vec<usize>>>() else{
+                continue;
+            };
+
+            if edges.iter().any(|&edge| on_outline[connections[edge].i1] || on_outline[connections[edge].i2]){
+                continue;
+            }
+
+            for edge in edges{
+                connections[edge].is_edge = true;
+            }
+        }
+    }
+
+    if let Err(err) = SB::validate(&new_nodes, &connections, kind != PieceKind::Closed){
+        warn!("Slice is not a valid Soft Body: {}", err);
+        return Err(None);
+    }
+
+    // keeps the rest shape of the nodes, centered on the piece
+    let mut base_skeleton: Vec<Vec2> = nodes.iter().map(|&old| sb.base_skeleton[old]).collect();
+    let middle = base_skeleton.iter().sum::<Vec2>() / base_skeleton.len() as f32;
+
+    for vec in &mut base_skeleton{
+        *vec -= middle;
+    }
+
+    let mut piece = SB::from_parts(&new_nodes, &connections, &base_skeleton, sb.angle);
+    piece.material = sb.material.clone();
+    piece.preset = sb.preset;
+    piece.scale = sb.scale;
+    piece.target_scale = sb.target_scale;
+
+    return Ok(SBPiece{sb: piece, nodes: nodes.to_vec()});
+}
+
+// the connections around the outside of the nodes, found by walking it with the outside
+// on the left and always turning left as far as possible. Err has the node when the walk
+// has to go through it twice, then there is no single outline
+fn outer_loop(
+    positions: &[Vec2],
+    connections: &[SBConnection],
+) -> Result<Vec<usize>, Option<usize>>{
+    let mut node_connections: Vec<Vec<usize>> = vec![Vec::new(); positions.len()];
+
+    for (index, connection) in connections.iter().enumerate(){
+        node_connections[connection.i1].push(index);
+        node_connections[connection.i2].push(index);
+    }
+
+    // the leftmost node is on the outside, and coming up from below the outline goes on clockwise
+    let start = (0..positions.len()).min_by(|&a, &b| positions[a].x.total_cmp(&positions[b].x).then(positions[a].y.total_cmp(&positions[b].y))).ok_or(None)?;
+
+    let mut node = start;
+    let mut back = Vec2::NEG_Y;
+
+    let mut edges = Vec::new();
+    let mut visited = vec![false; positions.len()];
+
+    loop{
+        let other_node = |edge: usize| if connections[edge].i1 == node {connections[edge].i2} else {connections[edge].i1};
+
+        // clockwise from the way back, the way back itself being last
+        let turn = |edge: usize| {
+            let dir = positions[other_node(edge)] - positions[node];
+            let angle = -back.perp_dot(dir).atan2(back.dot(dir));
+
+            if angle <= 0.0 {angle + TAU} else {angle}
+        };
+
+        let edge = node_connections[node].iter().copied().min_by(|&a, &b| turn(a).total_cmp(&turn(b))).ok_or(None)?;
+
+        if node == start && edges.first() == Some(&edge){
+            break;
+        }
+
+        if visited[node]{
+            return Err(Some(node));
+        }
+
+        visited[node] = true;
+        edges.push(edge);
+
+        let next = other_node(edge);
+        back = positions[node] - positions[next];
+        node = next;
+    }
+
+    return Ok(edges);
+}
+
+// the texture of the nodes that made it into the piece
+pub fn piece_texture(
+    texture: &SBTexture,
+    nodes: &[usize],
+) -> SBTexture{
+    let new_index: HashMap<usize, usize> = nodes.iter().enumerate().map(|(new, &old)| (old, new)).collect();
+
+    let triangles = texture.triangles.iter().filter_map(|triangle| {
+        Some([*new_index.get(&triangle[0])?, *new_index.get(&triangle[1])?, *new_index.get(&triangle[2])?])
+    }).collect();
+
+    return SBTexture{
+        path: texture.path.clone(),
+        uvs: nodes.iter().map(|&old| texture.uvs[old]).collect(),
+        triangles,
+    };
+}
+
+fn switch_tool(
+    input: Res<ButtonInput<KeyCode>>,
+    mut tool: ResMut<SBTool>,
+){
+    if !input.just_pressed(KeyCode::KeyX){
+        return;
+    }
+
+    *tool = match *tool{
+        SBTool::Drag => SBTool::Slice,
+        SBTool::Slice => SBTool::Drag,
+    };
+
+    info!("Left mouse button {:?}s", *tool);
+}
+
+fn slice_line(
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    tool: Res<SBTool>,
+    mut start: Local<Option<Vec2>>,
+    mut gizmos: Gizmos,
+    mut input_writer: EventWriter<SBInput>,
+){
+    if *tool != SBTool::Slice{
+        *start = None;
+        return;
+    }
+
+    let Some(mouse_position) = q_windows.single().cursor_position() else{
+        return;
+    };
+
+    let mut pos = mouse_position - HALF_DIM;
+    pos.y = -pos.y;
+
+    if mouse_input.just_pressed(MouseButton::Left){
+        *start = Some(pos);
+    }
+
+    let Some(from) = *start else{
+        return;
+    };
+
+    if mouse_input.pressed(MouseButton::Left){
+        gizmos.line_2d(from, pos, Color::srgb(1.0, 0.3, 0.3));
+        return;
+    }
+
+    *start = None;
+
+    input_writer.send(SBInput::Slice{from, to: pos});
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+    use crate::shapes::*;
+
+    fn total_mass(sb: &SB) -> f32{
+        return sb.nodes.iter().map(|node| node.mass).sum();
+    }
+
+    #[test]
+    fn lattice_cut_in_half_gives_two_closed_pieces(){
+        let (nodes, connections) = lattice(100.0, 60.0, 4, 2, Bracing::Cross);
+        let sb = SB::try_new(&nodes, &connections, false).unwrap();
+
+        // between the middle column and the one right of it
+        let pieces = slice_sb(&sb, Vec2::new(12.5, -100.0), Vec2::new(12.5, 100.0)).unwrap();
+
+        assert_eq!(pieces.len(), 2);
+        assert_eq!(pieces.iter().map(|piece| piece.sb.node_num).sum::<usize>(), sb.node_num);
+
+        for piece in &pieces{
+            assert_eq!(piece.sb.boundary.len(), 1);
+            assert!(piece.sb.chains.is_empty());
+        }
+    }
+
+    #[test]
+    fn line_missing_the_body_cuts_nothing(){
+        let (nodes, connections) = lattice(100.0, 60.0, 4, 2, Bracing::Cross);
+        let sb = SB::try_new(&nodes, &connections, false).unwrap();
+
+        assert!(slice_sb(&sb, Vec2::new(80.0, -100.0), Vec2::new(80.0, 100.0)).is_none());
+    }
+
+    #[test]
+    fn pinched_piece_survives(){
+        // two triangles meeting at the bottom middle node, held apart by the top edge
+        let nodes = vec![
+            SBNode::new(Vec2::new(0.0, 0.0)),
+            SBNode::new(Vec2::new(-20.0, -10.0)),
+            SBNode::new(Vec2::new(-20.0, 10.0)),
+            SBNode::new(Vec2::new(20.0, 10.0)),
+            SBNode::new(Vec2::new(20.0, -10.0)),
+        ];
+        let connections = vec![
+            SBConnection::new(1, 2, true, 20.0),
+            SBConnection::new(2, 3, true, 40.0),
+            SBConnection::new(3, 4, true, 20.0),
+            SBConnection::new(4, 0, true, 22.4),
+            SBConnection::new(0, 1, true, 22.4),
+            SBConnection::new(0, 2, false, 22.4),
+            SBConnection::new(0, 3, false, 22.4),
+        ];
+        let sb = SB::try_new(&nodes, &connections, false).unwrap();
+
+        // only the top edge, above the middle node
+        let pieces = slice_sb(&sb, Vec2::new(0.0, 5.0), Vec2::new(0.0, 20.0)).unwrap();
+
+        assert_eq!(pieces.len(), 2);
+
+        for piece in &pieces{
+            assert_eq!(piece.sb.node_num, 3);
+            assert_eq!(piece.sb.boundary.len(), 1);
+            assert!(piece.nodes.contains(&0));
+        }
+
+        // the middle node is in both, with half its mass each
+        let mass: f32 = pieces.iter().map(|piece| total_mass(&piece.sb)).sum();
+
+        assert!((mass - total_mass(&sb)).abs() < 1e-4);
+    }
+}
@@ -0,0 +1,193 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+use crate::settings::*;
+use crate::soft_body::*;
+use crate::sprite::*;
+use crate::rigid::*;
+use crate::fluid::*;
+use crate::water::*;
+use crate::material::*;
+
+pub const SNAPSHOT_VERSION: u32 = 2;
+pub const SNAPSHOT_PATH: &str = "snapshot.ron";
+
+// F5 saves the world to SNAPSHOT_PATH, F9 loads it back.
+// Loading goes through SBInput so it also ends up in recordings
+pub struct SBSnapshotPlugin;
+
+impl Plugin for SBSnapshotPlugin{
+    fn build(&self, app: &mut App){
+        app.add_systems(Update, snapshot_shortcuts.in_set(SBSet::Input));
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SBSnapshot{
+    pub nodes: Vec<SBNode>,
+    pub connections: Vec<SBConnection>,
+    pub base_skeleton: Vec<Vec2>,
+    pub angle: f32,
+    pub material: SBMaterial,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub preset: Option<SBPreset>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub texture: Option<SBTexture>,
+    #[serde(default)]
+    pub open: bool,
+    // the springs and skeleton are saved scaled already, these only matter while it is still growing
+    #[serde(default = "unit_scale")]
+    pub scale: f32,
+    #[serde(default = "unit_scale")]
+    pub target_scale: f32,
+}
+
+fn unit_scale() -> f32{
+    return 1.0;
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct WorldSnapshot{
+    pub version: u32,
+    // step the snapshot was taken at, only informational
+    pub step: u64,
+    pub settings: SBSettings,
+    pub bodies: Vec<SBSnapshot>,
+    #[serde(default)]
+    pub rigid_bodies: Vec<RigidBody>,
+    #[serde(default)]
+    pub fluid: Vec<FluidParticle>,
+    #[serde(default)]
+    pub water: Vec<WaterRegion>,
+}
+
+impl WorldSnapshot{
+    pub fn capture<'a>(
+        bodies: impl Iterator<Item = (&'a SB, Option<&'a SBTexture>)>,
+        rigid_bodies: impl Iterator<Item = &'a RigidBody>,
+        fluids: impl Iterator<Item = &'a Fluid>,
+        water: impl Iterator<Item = &'a WaterRegion>,
+        settings: &SBSettings,
+        step: SimStep,
+    ) -> Self{
+        let bodies = bodies.map(|(sb, texture)| SBSnapshot{
+            nodes: sb.nodes.clone(),
+            connections: sb.connections.clone(),
+            base_skeleton: sb.base_skeleton.clone(),
+            angle: sb.angle,
+            material: sb.material.clone(),
+            preset: sb.preset,
+            texture: texture.cloned(),
+            open: !sb.chains.is_empty(),
+            scale: sb.scale,
+            target_scale: sb.target_scale,
+        }).collect();
+
+        return Self{
+            version: SNAPSHOT_VERSION,
+            step: step.0,
+            settings: settings.clone(),
+            bodies,
+            rigid_bodies: rigid_bodies.cloned().collect(),
+            fluid: fluids.flat_map(|fluid| fluid.particles.iter().cloned()).collect(),
+            water: water.cloned().collect(),
+        };
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String>{
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()).map_err(|err| err.to_string())?;
+
+        return fs::write(path, text).map_err(|err| err.to_string());
+    }
+
+    pub fn load(path: &str) -> Result<Self, String>{
+        let text = fs::read_to_string(path).map_err(|err| err.to_string())?;
+        let snapshot: WorldSnapshot = ron::from_str(&text).map_err(|err| err.to_string())?;
+
+        if snapshot.version != SNAPSHOT_VERSION{
+            return Err(format!("unsupported snapshot version {} (expected {})", snapshot.version, SNAPSHOT_VERSION));
+        }
+
+        for (index, body) in snapshot.bodies.iter().enumerate(){
+            SB::validate(&body.nodes, &body.connections, body.open).map_err(|err| format!("body {}: {}", index, err))?;
+
+            if body.base_skeleton.len() != body.nodes.len(){
+                return Err(format!("body {}: {} skeleton points for {} nodes", index, body.base_skeleton.len(), body.nodes.len()));
+            }
+        }
+
+        return Ok(snapshot);
+    }
+}
+
+// despawns the current bodies and spawns the ones from the snapshot with fresh render children.
+// settings are left to the caller
+pub fn restore_snapshot(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    existing: impl Iterator<Item = Entity>,
+    snapshot: &WorldSnapshot,
+){
+    for entity in existing{
+        commands.entity(entity).despawn_recursive();
+    }
+
+    for body in &snapshot.bodies{
+        let mut soft_body = SB::from_parts(&body.nodes, &body.connections, &body.base_skeleton, body.angle);
+        soft_body.material = body.material.clone();
+        soft_body.preset = body.preset;
+        soft_body.scale = body.scale;
+        soft_body.target_scale = body.target_scale;
+
+        let entity = spawn_sb_entity(commands, meshes, materials, soft_body);
+
+        if let Some(texture) = &body.texture{
+            commands.entity(entity).insert(texture.clone());
+        }
+    }
+
+    for rigid in &snapshot.rigid_bodies{
+        spawn_rigid_entity(commands, meshes, materials, rigid.clone());
+    }
+
+    if !snapshot.fluid.is_empty(){
+        spawn_fluid_entity(commands, snapshot.fluid.clone());
+    }
+
+    for region in &snapshot.water{
+        spawn_water_entity(commands, meshes, materials, region.clone());
+    }
+
+    info!("Restored {} Soft Bodies, {} Rigid Bodies, {} fluid particles and {} water regions", snapshot.bodies.len(), snapshot.rigid_bodies.len(), snapshot.fluid.len(), snapshot.water.len());
+}
+
+fn snapshot_shortcuts(
+    input: Res<ButtonInput<KeyCode>>,
+    sb_query: Query<(&SB, Option<&SBTexture>)>,
+    rigid_query: Query<&RigidBody>,
+    fluid_query: Query<&Fluid>,
+    water_query: Query<&WaterRegion>,
+    settings: Res<SBSettings>,
+    step: Res<SimStep>,
+    mut input_writer: EventWriter<SBInput>,
+){
+    if input.just_pressed(KeyCode::F5){
+        let snapshot = WorldSnapshot::capture(sb_query.iter(), rigid_query.iter(), fluid_query.iter(), water_query.iter(), &settings, *step);
+
+        match snapshot.save(SNAPSHOT_PATH){
+            Ok(()) => info!("Saved {} Soft Bodies to {}", snapshot.bodies.len(), SNAPSHOT_PATH),
+            Err(err) => error!("Could not save snapshot: {}", err),
+        }
+    }
+
+    if input.just_pressed(KeyCode::F9){
+        match WorldSnapshot::load(SNAPSHOT_PATH){
+            Ok(snapshot) => {
+                input_writer.send(SBInput::LoadSnapshot(Box::new(snapshot)));
+            }
+            Err(err) => error!("Could not load snapshot: {}", err),
+        }
+    }
+}
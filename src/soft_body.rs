@@ -5,10 +5,25 @@ use bevy::{
     sprite::{MaterialMesh2dBundle, Mesh2dHandle},
 };
 use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
 use std::f32::NAN;
+use std::collections::HashMap;
+use std::fmt;
 // use rand::Rng;
 
 use crate:: settings:: *;
+use crate::snapshot::*;
+use crate::definition::*;
+use crate::svg::*;
+use crate::meshing::*;
+use crate::contact::*;
+use crate::rigid::*;
+use crate::fluid::*;
+use crate::material::*;
+use crate::water::*;
+use crate::field::*;
+use crate::slice::*;
+use crate::sprite::*;
 
 pub struct SBPlugin;
 
@@ -18,26 +33,128 @@ impl Plugin for SBPlugin{
             #[cfg(not(target_arch = "wasm32"))]
             Wireframe2dPlugin,
         ))
-        .add_systems(Update, (spawn_sb, update_processes, update_sb_draw))
-        .add_systems(Update, interact);
+        .init_resource::<SBSettings>()
+        .init_resource::<SimStep>()
+        .init_resource::<SBContacts>()
+        .init_resource::<SBTool>()
+        .add_event::<SBInput>()
+        .configure_sets(Update, (SBSet::Input, SBSet::Apply, SBSet::Simulate).chain())
+        .add_systems(Update, (spawn_sb, interact, change_settings).in_set(SBSet::Input))
+        .add_systems(Update, apply_sb_inputs.in_set(SBSet::Apply))
+        .add_systems(Update, update_processes.in_set(SBSet::Simulate))
+        .add_systems(Update, update_sb_draw.after(SBSet::Simulate));
     }
 }
 
-#[derive(Clone)]
-pub struct DistIndex{
-    pub dist: f32,
-    pub index: usize,
+// input systems only send SBInput events, which get applied in a fixed
+// order right before the simulation step. Keeps runs reproducible
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum SBSet{
+    Input,
+    Apply,
+    Simulate,
 }
 
-impl DistIndex{
-    fn new(dist: f32, index: usize) -> Self{
-        return DistIndex{
-            dist: dist,
-            index: index,
+// what the left mouse button does, X switches it
+#[derive(Resource, Default, Clone, Copy, Debug, PartialEq)]
+pub enum SBTool{
+    #[default]
+    Drag,
+    Slice,
+}
+
+// how many times update_processes has stepped the simulation
+#[derive(Resource, Default, Clone, Copy, Debug)]
+pub struct SimStep(pub u64);
+
+// everything from the outside that changes the simulation
+#[derive(Event, Clone, Debug, Serialize, Deserialize)]
+pub enum SBInput{
+    // the whole definition is stored so recordings dont depend on the shape files.
+    // source is the file it came from, if any
+    Spawn{
+        definition: Box<SBDefinition>,
+        source: Option<String>,
+        // the material the definition has is used when there is none
+        #[serde(default)]
+        preset: Option<SBPreset>,
+    },
+    // a shape file changed, so every body spawned from it gets replaced
+    Reload{
+        definition: Box<SBDefinition>,
+        source: String,
+    },
+    SpawnRigid{
+        body: Box<RigidBody>,
+    },
+    SpawnFluid{
+        particles: Vec<Vec2>,
+    },
+    SpawnWater{
+        region: WaterRegion,
+    },
+    Drag{
+        pos: Vec2,
+    },
+    // for the body at `pos`
+    SetPreset{
+        pos: Vec2,
+        preset: SBPreset,
+    },
+    // gives the body at `pos` the next charge
+    Charge{
+        pos: Vec2,
+    },
+    // scales the rest shape of the body at `pos`
+    Inflate{
+        pos: Vec2,
+        factor: f32,
+    },
+    // cuts every body the line goes through
+    Slice{
+        from: Vec2,
+        to: Vec2,
+    },
+    Settings(SBSettings),
+    LoadSnapshot(Box<WorldSnapshot>),
+}
+
+// what is wrong with the nodes and connections handed to SB::try_new, or with the
+// definition they come from. Connections are referred to by their index in the list
+#[derive(Clone, Debug, PartialEq)]
+pub enum SBError{
+    TooFewNodes{count: usize},
+    IndexOutOfRange{connection: usize, index: usize},
+    SelfConnection{connection: usize},
+    DuplicateConnection{first: usize, second: usize},
+    ZeroLengthSpring{connection: usize},
+    InvalidMass{node: usize},
+    // a node with a single edge, so the outline doesnt close there. Fine for open bodies
+    OpenEdgeLoop{node: usize},
+    // a node with more than two edges, so the outline doesnt know where to go
+    NonManifoldEdge{node: usize},
+    // the interior nodes of a meshed outline need a distance above 0 between them
+    InvalidSpacing{spacing: f32},
+}
+
+impl fmt::Display for SBError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        match self{
+            SBError::TooFewNodes{count} => write!(f, "soft body has {} nodes, it needs at least {}", count, MIN_NODES),
+            SBError::IndexOutOfRange{connection, index} => write!(f, "connection {} uses node {} which does not exist", connection, index),
+            SBError::SelfConnection{connection} => write!(f, "connection {} connects a node to itself", connection),
+            SBError::DuplicateConnection{first, second} => write!(f, "connections {} and {} connect the same nodes", first, second),
+            SBError::ZeroLengthSpring{connection} => write!(f, "connection {} has no resting length", connection),
+            SBError::InvalidMass{node} => write!(f, "node {} needs a mass above 0", node),
+            SBError::OpenEdgeLoop{node} => write!(f, "the edges through node {} do not form a closed loop", node),
+            SBError::NonManifoldEdge{node} => write!(f, "node {} has more than two edges", node),
+            SBError::InvalidSpacing{spacing} => write!(f, "interior spacing {} has to be above 0", spacing),
         }
     }
 }
 
+impl std::error::Error for SBError{}
+
 #[derive(Component)]
 pub struct SB{
     pub nodes: Vec<SBNode>,
@@ -45,16 +162,100 @@ pub struct SB{
     pub base_skeleton: Vec<Vec2>,
     pub base_skeleton_norm: Vec<Vec2>,
     pub skeleton: Vec<Vec2>,
-
-    pub node_num: u8,
+    // the closed outlines as indices into connections, in order. Outer loops are wound
+    // clockwise and holes counter clockwise, so the outside of the body is always on the
+    // left. the edge connections are flipped to match, so i1 -> i2 always goes along the loop
+    pub boundary: Vec<Vec<usize>>,
+    // the open polylines (ropes, hair) as indices into connections, end to end. They have no
+    // inside, so they collide by distance, see capsule_collision
+    pub chains: Vec<Vec<usize>>,
+    // every node on a chain, in order
+    pub chain_nodes: Vec<usize>,
+
+    pub node_num: usize,
+    // the closest nodes to every node in the rest shape, get_rel_center averages them
+    pub neighbours: Vec<Vec<usize>>,
     pub bounding_box: BoundingBox,
     pub center: Vec2,
     pub angle: f32,
+    pub material: SBMaterial,
+    // the preset the body was given when it spawned or with N. It outlasts a reload
+    // of the shape file, the body keeps its material and only takes the new shape
+    pub preset: Option<SBPreset>,
+    // how big the rest shape is next to the one the body was spawned with, and how big it
+    // is heading for. The springs and skeleton get scaled along the way, see inflation_flow
+    pub scale: f32,
+    pub target_scale: f32,
 }
 
 impl SB{
+    // checks the nodes and connections before building the body, SB::new takes them as they are.
+    // `open` lets the edges end somewhere instead of closing into outlines
+    pub fn try_new(nodes: &Vec<SBNode>, connections: &Vec<SBConnection>, open: bool) -> Result<Self, SBError>{
+        SB::validate(nodes, connections, open)?;
+
+        return Ok(SB::new(nodes, connections));
+    }
+
+    pub fn validate(nodes: &[SBNode], connections: &[SBConnection], open: bool) -> Result<(), SBError>{
+        if nodes.len() < MIN_NODES{
+            return Err(SBError::TooFewNodes{count: nodes.len()});
+        }
+
+        for (index, node) in nodes.iter().enumerate(){
+            if !(node.mass > 0.0) || !node.mass.is_finite(){
+                return Err(SBError::InvalidMass{node: index});
+            }
+        }
+
+        let mut seen: HashMap<(usize, usize), usize> = HashMap::new();
+
+        // every node on the outline needs exactly two edges, on an open chain the ends get one.
+        // which way they point gets fixed by SB::new
+        let mut edge_count = vec![0; nodes.len()];
+
+        for (index, connection) in connections.iter().enumerate(){
+            for node_index in [connection.i1, connection.i2]{
+                if node_index >= nodes.len(){
+                    return Err(SBError::IndexOutOfRange{connection: index, index: node_index});
+                }
+            }
+
+            if connection.i1 == connection.i2{
+                return Err(SBError::SelfConnection{connection: index});
+            }
+
+            let key = (connection.i1.min(connection.i2), connection.i1.max(connection.i2));
+
+            if let Some(first) = seen.insert(key, index){
+                return Err(SBError::DuplicateConnection{first, second: index});
+            }
+
+            if !(connection.resting_length > f32::EPSILON){
+                return Err(SBError::ZeroLengthSpring{connection: index});
+            }
+
+            if connection.is_edge{
+                edge_count[connection.i1] += 1;
+                edge_count[connection.i2] += 1;
+            }
+        }
+
+        for node in 0..nodes.len(){
+            if edge_count[node] > 2{
+                return Err(SBError::NonManifoldEdge{node});
+            }
+
+            if edge_count[node] == 1 && !open{
+                return Err(SBError::OpenEdgeLoop{node});
+            }
+        }
+
+        return Ok(());
+    }
+
     fn new(nodes: &Vec<SBNode>, connections: &Vec<SBConnection>) -> Self{
-        let node_num : u8 = nodes.len() as u8; 
+        let node_num = nodes.len();
 
         let mut center = Vec2::ZERO;
 
@@ -64,25 +265,57 @@ impl SB{
 
         center /= node_num as f32;        
 
-        let mut base_skeleton: Vec<Vec2> = vec![Vec2::ZERO; node_num as usize];
-        let mut base_skeleton_norm: Vec<Vec2> = vec![Vec2::ZERO; node_num as usize];
+        // the rest shape is the shape it starts in
+        let base_skeleton: Vec<Vec2> = nodes.iter().map(|node| node.read_pos - center).collect();
+
+        return SB::with_skeleton(nodes, connections, base_skeleton, center, 0.0);
+    }
 
+    // rebuilds a body from its saved state, the rest is recomputed
+    pub fn from_parts(nodes: &[SBNode], connections: &[SBConnection], base_skeleton: &[Vec2], angle: f32) -> Self{
+        let center = nodes.iter().map(|node| node.read_pos).sum::<Vec2>() / nodes.len() as f32;
 
-        for i in 0..(node_num as usize){
-            base_skeleton[i] = nodes[i].read_pos - center;
-            base_skeleton_norm[i] = (nodes[i].read_pos - center).normalize();
-        }
+        let mut sb = SB::with_skeleton(nodes, connections, base_skeleton.to_vec(), center, angle);
+
+        sb.update_bounding_box();
+
+        return sb;
+    }
+
+    // everything else follows from the nodes, connections and rest shape
+    fn with_skeleton(nodes: &[SBNode], connections: &[SBConnection], base_skeleton: Vec<Vec2>, center: Vec2, angle: f32) -> Self{
+        let node_num = nodes.len();
+
+        // a node right on the center has no direction
+        let base_skeleton_norm: Vec<Vec2> = base_skeleton.iter().map(|vec| vec.normalize_or_zero()).collect();
+
+        let neighbours = closest_neighbours(&base_skeleton);
+
+        let mut connections = connections.to_vec();
+        let (boundary, chains) = boundary_loops(nodes, &mut connections);
+
+        let mut chain_nodes: Vec<usize> = chains.iter().flatten().flat_map(|&edge| [connections[edge].i1, connections[edge].i2]).collect();
+        chain_nodes.sort_unstable();
+        chain_nodes.dedup();
 
         let mut sb: SB = SB{
-            nodes: nodes.clone(),
-            connections: connections.clone(),
+            nodes: nodes.to_vec(),
+            connections: connections,
             base_skeleton: base_skeleton,
             base_skeleton_norm: base_skeleton_norm,
-            skeleton: vec![Vec2::ZERO; node_num as usize],
+            skeleton: vec![Vec2::ZERO; node_num],
+            boundary: boundary,
+            chains,
+            chain_nodes,
             node_num: node_num,
+            neighbours: neighbours,
             bounding_box: BoundingBox::zero(),
             center: center,
-            angle: 0.0,
+            angle,
+            material: SBMaterial::default(),
+            preset: None,
+            scale: 1.0,
+            target_scale: 1.0,
         };
 
         sb.update_skeleton();
@@ -90,25 +323,43 @@ impl SB{
         return sb;
     }
 
-    fn get_rel_center(&self, node_index:usize) -> Vec2{
-        let mut dist_index_pairs = vec![DistIndex::new(0.0, 0); self.node_num as usize];
-        let node_index_pos = self.nodes[node_index].read_pos;
-        
-        for i in 0..self.node_num as usize{
-            dist_index_pairs[i] = DistIndex::new((self.nodes[i].read_pos - node_index_pos).length_squared(), i);
+    // swaps the material of a body that is already there, the connections and
+    // nodes lose whatever stiffness and mass they had of their own
+    pub fn set_material(&mut self, material: SBMaterial){
+        for connection in &mut self.connections{
+            connection.stiffness = material.stiffness;
+            connection.dampening = material.dampening;
         }
 
-        // yes I am sorry computer
-        dist_index_pairs.sort_by(|a, b| a.dist.total_cmp(&b.dist));
+        for node in &mut self.nodes{
+            node.mass = material.node_mass();
+        }
+
+        self.material = material;
+    }
+
+    pub fn set_preset(&mut self, preset: SBPreset){
+        self.set_material(preset.material());
+        self.preset = Some(preset);
+    }
+
+    // sets the size the body grows or shrinks to by `factor`, it gets there over time
+    pub fn inflate(&mut self, factor: f32){
+        self.target_scale = (self.target_scale * factor).clamp(MIN_INFLATION, MAX_INFLATION);
+    }
+
+    // the center of the nodes around this one, which stays inside the body
+    // even where the body is concave
+    fn get_rel_center(&self, node_index:usize) -> Vec2{
+        let neighbours = &self.neighbours[node_index];
 
-        // gets the center from the closest 4 nodes
         let mut center = Vec2::ZERO;
 
-        for i in 0..4{
-            center += self.nodes[dist_index_pairs[i].index].read_pos;
+        for i in neighbours{
+            center += self.nodes[*i].read_pos;
         }
 
-        return center/4.0;
+        return center / (neighbours.len().max(1) as f32);
     }
 
     fn get_center(&self) -> Vec2{
@@ -124,10 +375,17 @@ impl SB{
     fn get_angle(&self) -> f32{
         let mut average_angle : f32 = 0.0;
 
-        for i1 in 0..(self.node_num as usize){
-            let vec1 = (self.nodes[i1].read_pos - self.center).normalize();
-            let vec2 = self.base_skeleton[i1].normalize();
+        // a hub node sitting on the center has no angle to give, so it doesnt count. The
+        // center is only worked out up to rounding, so on it means within HUB_TOLERANCE
+        let directions: Vec<(Vec2, Vec2)> = (0..self.node_num)
+            .filter(|&i1| self.base_skeleton[i1].length() > HUB_TOLERANCE)
+            .map(|i1| ((self.nodes[i1].read_pos - self.center).normalize_or_zero(), self.base_skeleton[i1].normalize()))
+            .filter(|(vec1, _)| *vec1 != Vec2::ZERO)
+            .collect();
+
+        let count = directions.len() as f32;
 
+        for (vec1, vec2) in directions{
             let dot = vec1.dot(vec2).clamp(-1.0, 1.0);
 
             let cross = vec1.perp_dot(vec2);
@@ -142,10 +400,10 @@ impl SB{
             }
 
             if angle - average_angle < PI{
-                average_angle += angle / (self.node_num as f32);
+                average_angle += angle / count;
             }
             else{
-                average_angle -= (TAU - angle) / (self.node_num as f32);
+                average_angle -= (TAU - angle) / count;
             }
         }
 
@@ -188,30 +446,86 @@ impl SB{
     }
 }
 
-#[derive(Clone)]
+// stiffness and dampening are the defaults for the connections,
+// which keep their own copy so they can be tuned one by one.
+// material.rs has named ones
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SBMaterial{
+    pub stiffness: f32,
+    pub dampening: f32,
+    pub skeleton_stiffness: f32,
+    // how much sideways impulse a contact can take, as a share of the one pushing them apart
+    pub friction: f32,
+    // how much of the speed it hits other bodies with it bounces back with
+    pub restitution: f32,
+    // shapes have about a node every half resting length, so the nodes weigh
+    // this many times DEFAULT_NODE_MASS
+    pub density: f32,
+    // how fast the rest shape gives in to being stretched past PLASTIC_YIELD, 0 springs back
+    pub plasticity: f32,
+    // like charges push each other away and opposite ones pull, see field.rs
+    pub charge: f32,
+}
+
+impl Default for SBMaterial{
+    fn default() -> Self{
+        Self{
+            stiffness: DEFAULT_STIFFNESS,
+            dampening: DEFAULT_DAMPENING,
+            skeleton_stiffness: SKELETON_STIFFNESS,
+            friction: 0.0,
+            restitution: COLLISION_RESTITUTION,
+            density: 1.0,
+            plasticity: 0.0,
+            charge: 0.0,
+        }
+    }
+}
+
+impl SBMaterial{
+    pub fn node_mass(&self) -> f32{
+        return DEFAULT_NODE_MASS * self.density;
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SBNode{
     pub read_pos: Vec2,
     pub write_pos: Vec2,
     pub vel: Vec2,
+    // older snapshots have no masses, every node weighed the same back then
+    #[serde(default = "default_node_mass")]
+    pub mass: f32,
+}
+
+fn default_node_mass() -> f32{
+    return DEFAULT_NODE_MASS;
 }
 
 impl SBNode{
-    fn new(pos: Vec2) -> Self{
-        Self{read_pos:pos, write_pos:pos, vel: Vec2::ZERO}
+    pub fn new(pos: Vec2) -> Self{
+        Self{read_pos:pos, write_pos:pos, vel: Vec2::ZERO, mass: DEFAULT_NODE_MASS}
+    }
+
+    pub fn inverse_mass(&self) -> f32{
+        return 1.0 / self.mass;
     }
 }
 
-#[derive(Clone)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SBConnection{
     pub i1: usize,
     pub i2: usize,
     pub is_edge: bool,
     pub resting_length: f32,
+    pub stiffness: f32,
+    pub dampening: f32,
 }
 
 impl SBConnection{
-    fn new(i1: usize, i2: usize, is_edge: bool, resting_length: f32) -> Self{
-        Self{i1, i2, is_edge, resting_length}
+    pub fn new(i1: usize, i2: usize, is_edge: bool, resting_length: f32) -> Self{
+        Self{i1, i2, is_edge, resting_length, stiffness: DEFAULT_STIFFNESS, dampening: DEFAULT_DAMPENING}
     }
 }
 
@@ -225,6 +539,14 @@ impl BoundingBox{
     fn zero() -> Self{
         Self{min_pos: Vec2::ZERO, max_pos: Vec2::ZERO}
     }
+
+    pub fn grown(&self, margin: f32) -> BoundingBox{
+        return BoundingBox{min_pos: self.min_pos - margin, max_pos: self.max_pos + margin};
+    }
+
+    pub fn contains(&self, pt: Vec2) -> bool{
+        return pt.x >= self.min_pos.x && pt.x <= self.max_pos.x && pt.y >= self.min_pos.y && pt.y <= self.max_pos.y;
+    }
 }
 
 #[derive(Component, Default, Reflect, Clone)]
@@ -239,6 +561,133 @@ struct ConnectionIndex{
 }
 
 
+// walks the edge connections into loops and winds them, outer loops clockwise and
+// holes (loops inside an odd number of others) counter clockwise. Connections that
+// point against their loop get flipped. Open chains get walked first, from one of
+// their ends, and come back on their own
+fn boundary_loops(
+    nodes: &[SBNode],
+    connections: &mut [SBConnection],
+) -> (Vec<Vec<usize>>, Vec<Vec<usize>>){
+    let mut node_edges: HashMap<usize, Vec<usize>> = HashMap::new();
+
+    for (index, connection) in connections.iter().enumerate(){
+        if connection.is_edge{
+            node_edges.entry(connection.i1).or_default().push(index);
+            node_edges.entry(connection.i2).or_default().push(index);
+        }
+    }
+
+    let mut used = vec![false; connections.len()];
+    let mut chains = Vec::new();
+    let mut loops = Vec::new();
+
+    // a chain end has a single edge. Sorted so the chains come out the same every time
+    let mut ends: Vec<usize> = node_edges.iter().filter(|(_, edges)| edges.len() == 1).map(|(&node, _)| node).collect();
+    ends.sort_unstable();
+
+    for end in ends{
+        let start = node_edges[&end][0];
+
+        if used[start]{
+            continue;
+        }
+
+        chains.push(walk_edges(connections, &node_edges, &mut used, start, end));
+    }
+
+    for start in 0..connections.len(){
+        if !connections[start].is_edge || used[start]{
+            continue;
+        }
+
+        let node = connections[start].i1;
+        loops.push(walk_edges(connections, &node_edges, &mut used, start, node));
+    }
+
+    let outlines: Vec<Vec<Vec2>> = loops.iter().map(|edge_loop| edge_loop.iter().map(|&edge| nodes[connections[edge].i1].read_pos).collect()).collect();
+
+    for (index, edge_loop) in loops.iter_mut().enumerate(){
+        let pt = outlines[index][0];
+
+        let depth = outlines.iter().enumerate().filter(|(other, outline)| *other != index && point_in_outline(pt, outline)).count();
+        let is_hole = depth % 2 == 1;
+
+        let clockwise = signed_area(&outlines[index]) < 0.0;
+
+        if clockwise == is_hole{
+            edge_loop.reverse();
+
+            for &edge in edge_loop.iter(){
+                let connection = &mut connections[edge];
+                std::mem::swap(&mut connection.i1, &mut connection.i2);
+            }
+        }
+    }
+
+    return (loops, chains);
+}
+
+// follows unused edges from `node` along `start` until it runs out, pointing each
+// connection the way it was walked
+fn walk_edges(
+    connections: &mut [SBConnection],
+    node_edges: &HashMap<usize, Vec<usize>>,
+    used: &mut [bool],
+    start: usize,
+    mut node: usize,
+) -> Vec<usize>{
+    let mut edges = Vec::new();
+    let mut edge = start;
+
+    loop{
+        used[edge] = true;
+
+        let connection = &mut connections[edge];
+
+        if connection.i1 != node{
+            std::mem::swap(&mut connection.i1, &mut connection.i2);
+        }
+
+        edges.push(edge);
+        node = connection.i2;
+
+        match node_edges[&node].iter().copied().find(|&next| !used[next]){
+            Some(next) => edge = next,
+            None => break,
+        }
+    }
+
+    return edges;
+}
+
+// for every point the indices of the 4 closest ones (itself included),
+// or all of them on smaller bodies. Only worked out once per body
+fn closest_neighbours(
+    points: &[Vec2],
+) -> Vec<Vec<usize>>{
+    let count = points.len().min(4);
+
+    return points.iter().map(|pt| {
+        // the best ones so far, closest first
+        let mut closest: Vec<(f32, usize)> = Vec::with_capacity(count + 1);
+
+        for (index, other) in points.iter().enumerate(){
+            let dist = pt.distance_squared(*other);
+
+            if closest.len() == count && dist >= closest[count - 1].0{
+                continue;
+            }
+
+            let position = closest.partition_point(|(closest_dist, _)| *closest_dist <= dist);
+            closest.insert(position, (dist, index));
+            closest.truncate(count);
+        }
+
+        closest.into_iter().map(|(_, index)| index).collect()
+    }).collect();
+}
+
 fn vec_rotate(
     vec: &Vec2,
     angle: f32,
@@ -267,9 +716,9 @@ fn axis_aligned_line_overlap(
     return min_l1 <= max_l2 && max_l1 >= min_l2;
 }
 
-fn bounding_box_collision(
-    bb1: &mut BoundingBox,
-    bb2: &mut BoundingBox,
+pub fn bounding_box_collision(
+    bb1: &BoundingBox,
+    bb2: &BoundingBox,
 ) -> bool{
     let thing = axis_aligned_line_overlap(bb1.min_pos.x, bb1.max_pos.x, bb2.min_pos.x, bb2.max_pos.x) && axis_aligned_line_overlap(bb1.min_pos.y, bb1.max_pos.y, bb2.min_pos.y, bb2.max_pos.y);
 
@@ -279,16 +728,26 @@ fn bounding_box_collision(
     return thing; 
 }
 
+// the contacts for every node of sb1 that is inside sb2
 fn soft_body_collision(
-    sb1: &mut SB,
-    sb2: &mut SB,
+    sb1: &SB,
+    sb2: &SB,
+    bodies: [Entity; 2],
+    contacts: &mut Vec<SBContact>,
 ){
-    if !bounding_box_collision(&mut sb1.bounding_box, &mut sb2.bounding_box){
+    if !bounding_box_collision(&sb1.bounding_box, &sb2.bounding_box){
         return;
     }
 
-    for counter in 0..(sb1.node_num as usize){
+    for counter in 0..sb1.node_num{
         let node = &sb1.nodes[counter];
+
+        // only the nodes in the overlap can be inside, saves walking every edge for the rest.
+        // chain nodes get capsule_collision instead
+        if !sb2.bounding_box.contains(node.read_pos) || sb1.chain_nodes.binary_search(&counter).is_ok(){
+            continue;
+        }
+
         if sb_point_intersection(node.read_pos, sb2){
             // println!("atleast heere");
             
@@ -303,88 +762,207 @@ fn soft_body_collision(
                 continue;
             }
 
-            // println!("{}", dot);
-            // dont we all love the rust borrow checker?
-            soft_body_collision_response(&mut sb1.nodes[counter], sb2, connection.i1, connection.i2, coll_pt, dot);
+            // push the node out to the edge, the edge takes the other side of it
+            let correction = coll_pt - node.read_pos;
+            let dot = dot.clamp(0.0, 1.0);
+            let (i1, i2) = (connection.i1, connection.i2);
+
+            contacts.extend(SBContact::new(bodies, [sb1, sb2], [[(counter, 1.0), (counter, 0.0)], [(i1, 1.0 - dot), (i2, dot)]], correction));
             
         }
     }
 
 }
 
-fn soft_body_collision_response(
-    node: &mut SBNode,
-    sb2: &mut SB,
-    con_pt1_index: usize,
-    con_pt2_index: usize,
-    coll_pos: Vec2,
-    dot: f32,
+// open chains have no inside, so their nodes are balls of NODE_RADIUS and their edges
+// capsules as thick. Chain nodes of sb1 get pushed off every edge of sb2 and the rest of
+// sb1's nodes off sb2's chains, the other way round is the call with them swapped
+fn capsule_collision(
+    sb1: &SB,
+    sb2: &SB,
+    bodies: [Entity; 2],
+    contacts: &mut Vec<SBContact>,
 ){
-    let vec = node.read_pos - coll_pos;
+    if sb1.chains.is_empty() && sb2.chains.is_empty(){
+        return;
+    }
 
-    let node_vec = -vec;
-    let con_pt1_vec = vec * (1.0 - dot);
-    let con_pt2_vec = vec * dot;
+    let margin = NODE_RADIUS * 2.0;
+    let reach = sb2.bounding_box.grown(margin);
 
-    node.write_pos += node_vec;
-    sb2.nodes[con_pt1_index].write_pos += con_pt1_vec;
-    sb2.nodes[con_pt2_index].write_pos += con_pt2_vec;
+    if !bounding_box_collision(&sb1.bounding_box, &reach){
+        return;
+    }
 
-    node.vel += node_vec;
-    sb2.nodes[con_pt1_index].vel += con_pt1_vec;
-    sb2.nodes[con_pt2_index].vel += con_pt2_vec;
-}
+    for counter in 0..sb1.node_num{
+        let pos = sb1.nodes[counter].read_pos;
+        let on_chain = sb1.chain_nodes.binary_search(&counter).is_ok();
 
+        if !reach.contains(pos) || (!on_chain && sb2.chains.is_empty()){
+            continue;
+        }
 
-// true if left and false if right
-fn line_pt_lateral(
-    pt: Vec2,
-    line_pt1: Vec2,
-    line_pt2: Vec2,
-) -> bool{
-    let ab: Vec2;
+        // a chain node that got all the way inside goes back out through the closest edge,
+        // the point across from sb2's center stands in for the body it doesnt have
+        if on_chain && sb_point_intersection(pos, sb2){
+            let (edge_pt, dist, edge, t) = get_closest_edge(pos, pos * 2.0 - sb2.center, sb2);
+            let connection = &sb2.connections[edge];
+
+            // the program probably found a faulty intersection
+            if dist >= connection.resting_length / 2.0 || dist <= f32::EPSILON{
+                continue;
+            }
+
+            let t = t.clamp(0.0, 1.0);
+            let correction = (edge_pt - pos) / dist * (dist + margin);
+
+            contacts.extend(SBContact::new(bodies, [sb1, sb2], [[(counter, 1.0), (counter, 0.0)], [(connection.i1, 1.0 - t), (connection.i2, t)]], correction));
+            continue;
+        }
+
+        // closed outlines only matter for chain nodes, soft_body_collision does the rest
+        let loops: &[Vec<usize>] = if on_chain {&sb2.boundary} else {&[]};
+
+        let mut closest: Option<(Vec2, f32, usize, f32)> = None;
+        let mut min_dist = margin;
+
+        for &edge in loops.iter().chain(sb2.chains.iter()).flatten(){
+            let connection = &sb2.connections[edge];
+            let (p1, p2) = (sb2.nodes[connection.i1].read_pos, sb2.nodes[connection.i2].read_pos);
+
+            let t = ((pos - p1).dot(p2 - p1) / (p2 - p1).length_squared()).clamp(0.0, 1.0);
+            let edge_pt = p1 + (p2 - p1) * t;
+            let dist = pos.distance(edge_pt);
+
+            if dist < min_dist{
+                min_dist = dist;
+                closest = Some((edge_pt, dist, edge, t));
+            }
+        }
+
+        let Some((edge_pt, dist, edge, t)) = closest else{
+            continue;
+        };
 
-    if line_pt2.y > line_pt1.y{
-        ab = line_pt2 - line_pt1;
+        if dist <= f32::EPSILON{
+            continue;
+        }
+
+        let connection = &sb2.connections[edge];
+        let correction = (pos - edge_pt) / dist * (margin - dist);
+
+        contacts.extend(SBContact::new(bodies, [sb1, sb2], [[(counter, 1.0), (counter, 0.0)], [(connection.i1, 1.0 - t), (connection.i2, t)]], correction));
     }
-    else{
-        ab = line_pt1 - line_pt2;
+}
+
+// where the segments a1 a2 and b1 b2 cross, as how far along each one. None when they
+// dont, touching counts as not crossing
+pub fn segment_intersection(
+    a1: Vec2,
+    a2: Vec2,
+    b1: Vec2,
+    b2: Vec2,
+) -> Option<(f32, f32)>{
+    let side_b1 = orient(a1, a2, b1);
+    let side_b2 = orient(a1, a2, b2);
+    let side_a1 = orient(b1, b2, a1);
+    let side_a2 = orient(b1, b2, a2);
+
+    if side_b1 * side_b2 >= 0.0 || side_a1 * side_a2 >= 0.0{
+        return None;
     }
 
-    let ap = pt - line_pt1;
+    let s = side_a1 / (side_a1 - side_a2);
+    let t = side_b1 / (side_b1 - side_b2);
 
-    return ab.perp_dot(ap) > 0.0;
+    return Some((s as f32, t as f32));
 }
 
-fn sb_point_intersection(
-    pt: Vec2,
-    sb: &mut SB,
-) -> bool{
-    let mut intersection_counter_y = 0;
+// catches edges crossing without either body having a node inside the other,
+// like two thin bodies lying across each other in an X
+fn edge_collision(
+    sb1: &SB,
+    sb2: &SB,
+    bodies: [Entity; 2],
+    contacts: &mut Vec<SBContact>,
+){
+    if !bounding_box_collision(&sb1.bounding_box, &sb2.bounding_box){
+        return;
+    }
 
-    for connection in &sb.connections{
-        if !connection.is_edge{
-            continue;
-        }
+    // only edges reaching into the overlap can cross
+    let overlap = BoundingBox{
+        min_pos: sb1.bounding_box.min_pos.max(sb2.bounding_box.min_pos),
+        max_pos: sb1.bounding_box.max_pos.min(sb2.bounding_box.max_pos),
+    };
 
-        let p1 = sb.nodes[connection.i1].read_pos;
-        let p2 = sb.nodes[connection.i2].read_pos;
+    let edges1: Vec<usize> = sb1.boundary.iter().flatten().copied().filter(|&edge| edge_in_box(sb1, edge, &overlap)).collect();
+    let edges2: Vec<usize> = sb2.boundary.iter().flatten().copied().filter(|&edge| edge_in_box(sb2, edge, &overlap)).collect();
 
-        if pt.y > p1.y.min(p2.y){
-            if pt.y <= p1.y.max(p2.y){
-                if pt.x <= p1.x.max(p2.x){
-                    let x_intersection = (pt.y - p1.y) * (p2.x - p1.x) / (p2.y - p1.y) + p1.x;
+    for &edge1 in &edges1{
+        for &edge2 in &edges2{
+            let (a1_index, a2_index) = (sb1.connections[edge1].i1, sb1.connections[edge1].i2);
+            let (b1_index, b2_index) = (sb2.connections[edge2].i1, sb2.connections[edge2].i2);
 
-                    if p1.x == p2.x || pt.x <= x_intersection{
-                        intersection_counter_y += 1;
-                    }
-                }
+            let (a1, a2) = (sb1.nodes[a1_index].read_pos, sb1.nodes[a2_index].read_pos);
+            let (b1, b2) = (sb2.nodes[b1_index].read_pos, sb2.nodes[b2_index].read_pos);
+
+            let Some((s, t)) = segment_intersection(a1, a2, b1, b2) else{
+                continue;
+            };
+
+            // a node inside the other body is already pushed out by soft_body_collision
+            if sb_point_intersection(a1, sb2) || sb_point_intersection(a2, sb2) || sb_point_intersection(b1, sb1) || sb_point_intersection(b2, sb1){
+                continue;
             }
-        }
 
+            // the outside is on the left of both edges
+            let normal1 = (a2 - a1).normalize_or_zero().perp();
+            let normal2 = (b2 - b1).normalize_or_zero().perp();
+
+            // how far the end of each edge that went through the other one is past it
+            let depth1 = (-(a1 - b1).dot(normal2)).max(-(a2 - b1).dot(normal2));
+            let depth2 = (-(b1 - a1).dot(normal1)).max(-(b2 - a1).dot(normal1));
+
+            // push out the shallower way, normal is the way sb1 has to go
+            let (normal, depth) = if depth1 <= depth2 {(normal2, depth1)} else {(-normal1, depth2)};
+
+            // the program probably found a faulty intersection
+            if depth >= sb1.connections[edge1].resting_length.min(sb2.connections[edge2].resting_length) / 2.0{
+                continue;
+            }
+
+            contacts.extend(SBContact::new(bodies, [sb1, sb2], [[(a1_index, 1.0 - s), (a2_index, s)], [(b1_index, 1.0 - t), (b2_index, t)]], normal * depth));
+        }
     }
+}
+
+fn edge_in_box(
+    sb: &SB,
+    edge: usize,
+    bounding_box: &BoundingBox,
+) -> bool{
+    let p1 = sb.nodes[sb.connections[edge].i1].read_pos;
+    let p2 = sb.nodes[sb.connections[edge].i2].read_pos;
+
+    return axis_aligned_line_overlap(p1.x.min(p2.x), p1.x.max(p2.x), bounding_box.min_pos.x, bounding_box.max_pos.x)
+        && axis_aligned_line_overlap(p1.y.min(p2.y), p1.y.max(p2.y), bounding_box.min_pos.y, bounding_box.max_pos.y);
+}
+
+// winding number over every boundary loop. Outer loops are clockwise and holes counter
+// clockwise so a hole cancels out the loop around it, and a body folded over itself
+// still counts as inside where it overlaps
+pub fn sb_point_intersection(
+    pt: Vec2,
+    sb: &SB,
+) -> bool{
+    let edges = sb.boundary.iter().flatten().map(|&edge| {
+        let connection = &sb.connections[edge];
+
+        (sb.nodes[connection.i1].read_pos, sb.nodes[connection.i2].read_pos)
+    });
 
-    return intersection_counter_y % 2 == 1;
+    return winding_number(pt, edges) != 0;
 }
 
 // returns the distance from edge and 
@@ -402,74 +980,67 @@ fn point_line_dist(
   return (line_pt1 + (line_pt2 - line_pt1) * t, t);
 }
 
-fn get_closest_edge(
+pub fn get_closest_edge(
     node_pos: Vec2,
     center: Vec2, 
-    sb: &mut SB,
+    sb: &SB,
 ) -> (Vec2, f32, usize, f32){
     let mut min_dist : f32 = 10000000.0; // distance to edge
     let mut best_pt : Vec2 = Vec2::ZERO; // point on edge
     let mut connection_index: usize = 0; // edge index
     let mut fin_dot:f32 = 0.0; // how far along the edge
 
-    let mut counter: usize = 0;
+    for edge_loop in &sb.boundary{
+        for &edge in edge_loop{
+            let connection = &sb.connections[edge];
 
-    for connection in &sb.connections{
+            let pt1 = &sb.nodes[connection.i1];
+            let pt2 = &sb.nodes[connection.i2];
 
-        // edges cannot be colliding
-        if !connection.is_edge{
-            counter += 1;
-            continue;
-        }
+            // dis from edge and how far along the edge is
+            let (closest_pt, dot) = point_line_dist(node_pos, pt1.read_pos, pt2.read_pos);
 
-        let pt1 = &sb.nodes[connection.i1];
-        let pt2 = &sb.nodes[connection.i2];
-
-        // dis from edge and how far along the edge is
-        let (closest_pt, dot) = point_line_dist(node_pos, pt1.read_pos, pt2.read_pos);
-
-        // make sure the point is near the line
-        if dot > 1.1 || dot < -0.1{
-            counter += 1;
-            // println!("dot skip");
-            continue;
-        }
+            // make sure the point is near the line
+            if dot > 1.1 || dot < -0.1{
+                // println!("dot skip");
+                continue;
+            }
 
-        // pls wind the points clockwise or something
-        let mut connection_normal = -(pt1.read_pos - pt2.read_pos).normalize().perp();
+            // the outside is on the left of every boundary loop, holes included, so this points out
+            let connection_normal = -(pt1.read_pos - pt2.read_pos).normalize().perp();
 
-        let center_to_point = (center - closest_pt).normalize();
+            let center_to_point = (center - closest_pt).normalize();
 
-        // make sure the center is facing the outside
-        if connection_normal.dot(center_to_point) < 0.2{
-            counter += 1;
-            continue;
-        }
+            // make sure the center is facing the outside
+            if connection_normal.dot(center_to_point) < 0.2{
+                continue;
+            }
 
-        let dist = (closest_pt - node_pos).length_squared();
+            let dist = (closest_pt - node_pos).length_squared();
 
-        if dist < min_dist{
-            best_pt = closest_pt;
-            min_dist = dist;
-            connection_index = counter;
-            fin_dot = dot;
-            // println!("new min dist {}, new conn index {}", min_dist, connection_index);
+            if dist < min_dist{
+                best_pt = closest_pt;
+                min_dist = dist;
+                connection_index = edge;
+                fin_dot = dot;
+                // println!("new min dist {}, new conn index {}", min_dist, connection_index);
+            }
         }
-
-        counter += 1;
     }
     // println!("pt1:{:?} dist:{:?} conn_i:{} dot:{}", best_pt, min_dist.sqrt(), connection_index, fin_dot);
     return (best_pt, min_dist.sqrt(), connection_index, fin_dot);
 }
 
 fn interact(
-    mut commands: Commands,
-    mut SB_query: Query<&mut SB>,
-    time: Res<Time>,
     q_windows: Query<&Window, With<PrimaryWindow>>,
     mouseInput: Res<ButtonInput<MouseButton>>,
-    keyInput: Res<ButtonInput<KeyCode>>,
+    tool: Res<SBTool>,
+    mut input_writer: EventWriter<SBInput>,
 ){
+    if *tool != SBTool::Drag{
+        return;
+    }
+
     let mut position = Vec2::new(0.0, 0.0);
 
     if let Some(mouse_position) = q_windows.single().cursor_position() {
@@ -484,182 +1055,303 @@ fn interact(
 
 
 
-    // is this ugly? yes. But hey I acknowledged it, and thats what matters
     if mouseInput.pressed(MouseButton::Left) {
-        let mut min_dist : f32 = 100000.0;        
-        
-        for sb in &mut SB_query{
-            for node in &sb.nodes{
-                let dist: f32 = (rel_position - node.read_pos).length();
-                
-                if dist < min_dist{
-                    min_dist = dist
-                }
+        input_writer.send(SBInput::Drag{pos: rel_position});
+    }
+}
+
+fn drag_closest_node(
+    SB_query: &mut Query<&mut SB>,
+    rel_position: Vec2,
+){
+    // is this ugly? yes. But hey I acknowledged it, and thats what matters
+    let mut min_dist : f32 = 100000.0;        
+    
+    for sb in SB_query.iter(){
+        for node in &sb.nodes{
+            let dist: f32 = (rel_position - node.read_pos).length();
+            
+            if dist < min_dist{
+                min_dist = dist
             }
         }
-        
-        for mut sb in &mut SB_query{
-            for mut node in &mut sb.nodes{
-                let dist: f32 = (rel_position - node.read_pos).length();
-                
-                if dist == min_dist{
-                    node.write_pos = rel_position;
-                    node.vel = Vec2::ZERO;
-                    break;
-                }
+    }
+    
+    for mut sb in SB_query.iter_mut(){
+        for mut node in &mut sb.nodes{
+            let dist: f32 = (rel_position - node.read_pos).length();
+            
+            if dist == min_dist{
+                node.write_pos = rel_position;
+                node.vel = Vec2::ZERO;
+                break;
             }
         }
     }
 }
 
+// the body `pos` is inside of, or failing that the one with a node
+// closest to it, as long as that is within a resting length
+pub fn body_at<'a>(
+    sb_query: &'a mut Query<&mut SB>,
+    pos: Vec2,
+) -> Option<Mut<'a, SB>>{
+    let inside = sb_query.iter().position(|sb| sb_point_intersection(pos, sb));
+
+    let closest = || sb_query.iter()
+        .map(|sb| sb.nodes.iter().map(|node| node.read_pos.distance(pos)).fold(f32::MAX, f32::min))
+        .enumerate()
+        .filter(|(_, dist)| *dist < DEFAULT_RESTING_LENGTH)
+        .min_by(|(_, dist1), (_, dist2)| dist1.total_cmp(dist2))
+        .map(|(index, _)| index);
+
+    let index = inside.or_else(closest)?;
+
+    return sb_query.iter_mut().nth(index);
+}
+
+fn change_settings(
+    input: Res<ButtonInput<KeyCode>>,
+    settings: Res<SBSettings>,
+    mut input_writer: EventWriter<SBInput>,
+){
+    let mut new_settings = settings.clone();
+
+    if input.just_pressed(KeyCode::KeyG){
+        if new_settings.gravity == Vec2::ZERO{
+            new_settings.gravity = GRAVITY;
+        }
+        else{
+            new_settings.gravity = Vec2::ZERO;
+        }
+    }
+
+    if input.just_pressed(KeyCode::KeyP){
+        new_settings.paused = !new_settings.paused;
+    }
+
+    if input.just_pressed(KeyCode::KeyI){
+        new_settings.body_gravity = if new_settings.body_gravity == 0.0 {BODY_GRAVITY} else {0.0};
+    }
+
+    if new_settings != *settings{
+        input_writer.send(SBInput::Settings(new_settings));
+    }
+}
+
 fn spawn_sb(
-    mut commands: Commands,
     input: Res<ButtonInput<KeyCode>>,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
+    shapes: Res<SBShapes>,
+    definitions: Res<Assets<SBDefinition>>,
+    drawings: Res<Assets<SBDrawing>>,
+    preset: Res<SBPresetSelection>,
+    mut input_writer: EventWriter<SBInput>,
 ){
     if !input.just_pressed(KeyCode::Space){
         return;
     }
 
-    // let parent = parent.single();
+    let (name, shape) = &shapes.shapes[shapes.selected];
+
+    match shape{
+        SBShape::Definition(handle) => {
+            let Some(definition) = definitions.get(handle) else{
+                warn!("Shape {} is not loaded (yet)", name);
+                return;
+            };
+
+            input_writer.send(SBInput::Spawn{definition: Box::new(definition.clone()), source: Some(name.clone()), preset: preset.0});
+        }
+        SBShape::Drawing(handle) => {
+            let Some(drawing) = drawings.get(handle) else{
+                warn!("Drawing {} is not loaded (yet)", name);
+                return;
+            };
+
+            for (index, definition) in drawing.bodies.iter().enumerate(){
+                input_writer.send(SBInput::Spawn{definition: Box::new(definition.clone()), source: Some(drawing_source(name, index)), preset: preset.0});
+            }
+        }
+    }
+}
+
+fn apply_sb_inputs(
+    mut commands: Commands,
+    mut input_reader: EventReader<SBInput>,
+    mut SB_query: Query<&mut SB>,
+    mut fluid_query: Query<&mut Fluid>,
+    body_entities: Query<Entity, Or<(With<SB>, With<RigidBody>, With<Fluid>, With<WaterRegion>)>>,
+    sb_sources: Query<(Entity, &SBSource)>,
+    sb_textures: Query<(Entity, Option<&SBTexture>), With<SB>>,
+    mut settings: ResMut<SBSettings>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+){
+    for input in input_reader.read(){
+        match input{
+            SBInput::Spawn{definition, source, preset} => {
+                let mut soft_body = match definition.to_sb(){
+                    Ok(soft_body) => soft_body,
+                    Err(err) => {
+                        error!("Could not spawn Soft Body: {}", err);
+                        continue;
+                    }
+                };
+
+                if let Some(preset) = preset{
+                    soft_body.set_preset(*preset);
+                }
+
+                let entity = spawn_sb_entity(&mut commands, &mut meshes, &mut materials, soft_body);
+
+                if let Some(source) = source{
+                    commands.entity(entity).insert(SBSource(source.clone()));
+                }
+
+                if let Some(texture) = &definition.texture{
+                    commands.entity(entity).insert(texture.clone());
+                }
+
+                info!("Spawned new Soft Body");
+            }
+            SBInput::Reload{definition, source} => {
+                for (entity, sb_source) in &sb_sources{
+                    if sb_source.0 != *source{
+                        continue;
+                    }
+
+                    let Ok(old_sb) = SB_query.get(entity) else{
+                        continue;
+                    };
+
+                    let soft_body = match definition.to_sb_at(old_sb){
+                        Ok(soft_body) => soft_body,
+                        Err(err) => {
+                            error!("Could not reload {}: {}", source, err);
+                            break;
+                        }
+                    };
+
+                    commands.entity(entity).despawn_recursive();
+
+                    let new_entity = spawn_sb_entity(&mut commands, &mut meshes, &mut materials, soft_body);
+                    commands.entity(new_entity).insert(SBSource(source.clone()));
+
+                    if let Some(texture) = &definition.texture{
+                        commands.entity(new_entity).insert(texture.clone());
+                    }
+                }
+
+                info!("Reloaded {}", source);
+            }
+            SBInput::SpawnRigid{body} => {
+                spawn_rigid_entity(&mut commands, &mut meshes, &mut materials, (**body).clone());
+
+                info!("Spawned new Rigid Body");
+            }
+            SBInput::SpawnFluid{particles} => {
+                let new_particles = particles.iter().map(|pos| FluidParticle::new(*pos));
+
+                // all the fluid goes into one entity so it mixes
+                if let Some(mut fluid) = fluid_query.iter_mut().next(){
+                    fluid.particles.extend(new_particles);
+                }
+                else{
+                    spawn_fluid_entity(&mut commands, new_particles.collect());
+                }
+
+                info!("Poured {} fluid particles", particles.len());
+            }
+            SBInput::SpawnWater{region} => {
+                spawn_water_entity(&mut commands, &mut meshes, &mut materials, region.clone());
+
+                info!("Added water");
+            }
+            SBInput::Drag{pos} => {
+                drag_closest_node(&mut SB_query, *pos);
+            }
+            SBInput::SetPreset{pos, preset} => {
+                let Some(mut sb) = body_at(&mut SB_query, *pos) else{
+                    continue;
+                };
+
+                // the charge isnt part of the feel, K sets it on its own
+                let charge = sb.material.charge;
+                sb.set_preset(*preset);
+                sb.material.charge = charge;
+
+                info!("Changed the material of a Soft Body");
+            }
+            SBInput::Charge{pos} => {
+                let Some(mut sb) = body_at(&mut SB_query, *pos) else{
+                    continue;
+                };
+
+                sb.material.charge = next_charge(sb.material.charge);
+
+                info!("Charged a Soft Body with {}", sb.material.charge);
+            }
+            SBInput::Inflate{pos, factor} => {
+                let Some(mut sb) = body_at(&mut SB_query, *pos) else{
+                    continue;
+                };
+
+                sb.inflate(*factor);
+            }
+            SBInput::Slice{from, to} => {
+                for (entity, texture) in &sb_textures{
+                    let Ok(sb) = SB_query.get(entity) else{
+                        continue;
+                    };
+
+                    let Some(pieces) = slice_sb(&sb, *from, *to) else{
+                        continue;
+                    };
+
+                    commands.entity(entity).despawn_recursive();
+
+                    // the pieces arent the shape in the file anymore, so they dont get reloaded
+                    for piece in pieces{
+                        let new_entity = spawn_sb_entity(&mut commands, &mut meshes, &mut materials, piece.sb);
 
+                        if let Some(texture) = texture{
+                            commands.entity(new_entity).insert(piece_texture(texture, &piece.nodes));
+                        }
+                    }
+
+                    info!("Sliced a Soft Body");
+                }
+            }
+            SBInput::Settings(new_settings) => {
+                *settings = new_settings.clone();
+            }
+            SBInput::LoadSnapshot(snapshot) => {
+                restore_snapshot(&mut commands, &mut meshes, &mut materials, body_entities.iter(), snapshot);
+
+                *settings = snapshot.settings.clone();
+            }
+        }
+    }
+}
+
+// spawns the soft body together with the sprites that draw it
+pub fn spawn_sb_entity(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    soft_body: SB,
+) -> Entity{
     let shape = Mesh2dHandle(meshes.add(Circle::new(NODE_RADIUS)));
     
     let color = Color::rgb(1.0, 1.0, 1.0);
 
-    // cube
-    // let node_vec = vec![
-    //     SBNode::new(Vec2::new(-DEFAULT_RESTING_LENGTH/2.0, DEFAULT_RESTING_LENGTH/2.0)),
-    //     SBNode::new(Vec2::new(DEFAULT_RESTING_LENGTH/2.0, DEFAULT_RESTING_LENGTH/2.0)),
-    //     SBNode::new(Vec2::new(-DEFAULT_RESTING_LENGTH/2.0, -DEFAULT_RESTING_LENGTH/2.0)),
-    //     SBNode::new(Vec2::new(DEFAULT_RESTING_LENGTH/2.0, -DEFAULT_RESTING_LENGTH/2.0)),
-    // ];
-
-    // let connection_vec = vec![
-    //     SBConnection::new(0, 1, true, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(0, 2, true, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(1, 3, true, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(2, 3, true, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(0, 3, false, (DEFAULT_RESTING_LENGTH*DEFAULT_RESTING_LENGTH*2.0).sqrt()),
-    //     SBConnection::new(1, 2, false, (DEFAULT_RESTING_LENGTH*DEFAULT_RESTING_LENGTH*2.0).sqrt())
-    // ];
-
-    // triangle
-    // let node_vec = vec![
-    //     SBNode::new(Vec2::new(0.0, 0.433 * DEFAULT_RESTING_LENGTH)),
-    //     SBNode::new(Vec2::new(-0.5*DEFAULT_RESTING_LENGTH, -0.433 * DEFAULT_RESTING_LENGTH)),
-    //     SBNode::new(Vec2::new(0.5*DEFAULT_RESTING_LENGTH, -0.433 * DEFAULT_RESTING_LENGTH)),
-    // ];
-
-    // let connection_vec = vec![
-    //     SBConnection::new(0,1,true, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(1,2,true, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(2,0,true, DEFAULT_RESTING_LENGTH),
-    // ];
-
-    // rectangle
-    // let node_vec = vec![
-    //     SBNode::new(Vec2::new(-DEFAULT_RESTING_LENGTH/2.0, DEFAULT_RESTING_LENGTH/2.0)),
-    //     SBNode::new(Vec2::new(DEFAULT_RESTING_LENGTH/2.0, DEFAULT_RESTING_LENGTH/2.0)),
-    //     SBNode::new(Vec2::new(-DEFAULT_RESTING_LENGTH/2.0, -DEFAULT_RESTING_LENGTH/2.0)),
-    //     SBNode::new(Vec2::new(DEFAULT_RESTING_LENGTH/2.0, -DEFAULT_RESTING_LENGTH/2.0)),
-    //     SBNode::new(Vec2::new(-DEFAULT_RESTING_LENGTH/2.0, -DEFAULT_RESTING_LENGTH)),
-    //     SBNode::new(Vec2::new(DEFAULT_RESTING_LENGTH/2.0, -DEFAULT_RESTING_LENGTH)),
-    // ];
-
-    // let connection_vec = vec![
-    //     SBConnection::new(0, 1, true, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(0, 2, true, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(1, 3, true, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(2, 3, false, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(2, 4, true, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(3, 5, true, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(4, 5, true, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(0, 3, false, (DEFAULT_RESTING_LENGTH*DEFAULT_RESTING_LENGTH*2.0).sqrt()),
-    //     SBConnection::new(1, 2, false, (DEFAULT_RESTING_LENGTH*DEFAULT_RESTING_LENGTH*2.0).sqrt()),
-    //     SBConnection::new(2, 5, false, (DEFAULT_RESTING_LENGTH*DEFAULT_RESTING_LENGTH*2.0).sqrt()),
-    //     SBConnection::new(3, 4, false, (DEFAULT_RESTING_LENGTH*DEFAULT_RESTING_LENGTH*2.0).sqrt())
-    // ];
-
-    //tetris 1
-    // let node_vec = vec![
-    //     SBNode::new(Vec2::new(-DEFAULT_RESTING_LENGTH*0.5, DEFAULT_RESTING_LENGTH*0.5)),
-    //     SBNode::new(Vec2::new(DEFAULT_RESTING_LENGTH*0.5, DEFAULT_RESTING_LENGTH*0.5)),
-    //     SBNode::new(Vec2::new(-DEFAULT_RESTING_LENGTH*0.5, -DEFAULT_RESTING_LENGTH*0.5)),
-    //     SBNode::new(Vec2::new(DEFAULT_RESTING_LENGTH*0.5, -DEFAULT_RESTING_LENGTH*0.5)),
-    //     SBNode::new(Vec2::new(-DEFAULT_RESTING_LENGTH*0.5, -DEFAULT_RESTING_LENGTH*1.5)),
-    //     SBNode::new(Vec2::new(DEFAULT_RESTING_LENGTH*0.5, -DEFAULT_RESTING_LENGTH*1.5)),
-    //     SBNode::new(Vec2::new(DEFAULT_RESTING_LENGTH*1.5, -DEFAULT_RESTING_LENGTH*0.5)),
-    //     SBNode::new(Vec2::new(DEFAULT_RESTING_LENGTH*1.5, -DEFAULT_RESTING_LENGTH*1.5)),
-    // ];
-
-    // let connection_vec = vec![
-    //     SBConnection::new(0, 1, true, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(0, 2, true, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(1, 3, true, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(2, 3, false, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(2, 4, true, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(3, 5, false, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(4, 5, true, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(3, 6, true, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(5, 7, true, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(6, 7, true, DEFAULT_RESTING_LENGTH),
-    //     SBConnection::new(0, 3, false, (DEFAULT_RESTING_LENGTH*DEFAULT_RESTING_LENGTH*2.0).sqrt()),
-    //     SBConnection::new(1, 2, false, (DEFAULT_RESTING_LENGTH*DEFAULT_RESTING_LENGTH*2.0).sqrt()),
-    //     SBConnection::new(2, 5, false, (DEFAULT_RESTING_LENGTH*DEFAULT_RESTING_LENGTH*2.0).sqrt()),
-    //     SBConnection::new(3, 4, false, (DEFAULT_RESTING_LENGTH*DEFAULT_RESTING_LENGTH*2.0).sqrt()),
-    //     SBConnection::new(3, 7, false, (DEFAULT_RESTING_LENGTH*DEFAULT_RESTING_LENGTH*2.0).sqrt()),
-    //     SBConnection::new(5, 6, false, (DEFAULT_RESTING_LENGTH*DEFAULT_RESTING_LENGTH*2.0).sqrt())
-    // ];
-
-    // tetris2
-    let node_vec = vec![
-        SBNode::new(Vec2::new(-DEFAULT_RESTING_LENGTH, DEFAULT_RESTING_LENGTH * 1.5)),
-        SBNode::new(Vec2::new(0.0, DEFAULT_RESTING_LENGTH * 1.5)),
-        SBNode::new(Vec2::new(-DEFAULT_RESTING_LENGTH, DEFAULT_RESTING_LENGTH * 0.5)),
-        SBNode::new(Vec2::new(0.0, DEFAULT_RESTING_LENGTH * 0.5)),
-        SBNode::new(Vec2::new(-DEFAULT_RESTING_LENGTH, -DEFAULT_RESTING_LENGTH * 0.5)),
-        SBNode::new(Vec2::new(0.0, -DEFAULT_RESTING_LENGTH * 0.5)),
-        SBNode::new(Vec2::new(-DEFAULT_RESTING_LENGTH, -DEFAULT_RESTING_LENGTH * 1.5)),
-        SBNode::new(Vec2::new(0.0, -DEFAULT_RESTING_LENGTH * 1.5)),
-        SBNode::new(Vec2::new(DEFAULT_RESTING_LENGTH, -DEFAULT_RESTING_LENGTH*0.5)),
-        SBNode::new(Vec2::new(DEFAULT_RESTING_LENGTH, -DEFAULT_RESTING_LENGTH*1.5)),
-    ];
-
-    let connection_vec = vec![
-        SBConnection::new(0, 1, true, DEFAULT_RESTING_LENGTH),
-        SBConnection::new(1, 3, true, DEFAULT_RESTING_LENGTH),
-        SBConnection::new(3, 5, true, DEFAULT_RESTING_LENGTH),
-        SBConnection::new(5, 8, true, DEFAULT_RESTING_LENGTH),
-        SBConnection::new(8, 9, true, DEFAULT_RESTING_LENGTH),
-        SBConnection::new(9, 7, true, DEFAULT_RESTING_LENGTH),
-        SBConnection::new(7, 6, true, DEFAULT_RESTING_LENGTH),
-        SBConnection::new(6, 4, true, DEFAULT_RESTING_LENGTH),
-        SBConnection::new(4, 2, true, DEFAULT_RESTING_LENGTH),
-        SBConnection::new(2, 0, true, DEFAULT_RESTING_LENGTH),
-        SBConnection::new(2, 3, false, DEFAULT_RESTING_LENGTH),
-        SBConnection::new(4, 5, false, DEFAULT_RESTING_LENGTH),
-        SBConnection::new(5, 7, false, DEFAULT_RESTING_LENGTH),
-        SBConnection::new(0, 3, false, DEFAULT_RESTING_LENGTH*1.41),
-        SBConnection::new(1, 2, false, DEFAULT_RESTING_LENGTH*1.41),
-        SBConnection::new(2, 5, false, DEFAULT_RESTING_LENGTH*1.41),
-        SBConnection::new(3, 4, false, DEFAULT_RESTING_LENGTH*1.41),
-        SBConnection::new(4, 7, false, DEFAULT_RESTING_LENGTH*1.41),
-        SBConnection::new(5, 6, false, DEFAULT_RESTING_LENGTH*1.41),
-        SBConnection::new(5, 9, false, DEFAULT_RESTING_LENGTH*1.41),
-        SBConnection::new(7, 8, false, DEFAULT_RESTING_LENGTH*1.41),
-    ];
-
-
-    // let base_skeleton = vec![];
-    // let skeleton = vec![];
-
-
-    let soft_body = SB::new(&node_vec, &connection_vec);
-
-
-    commands.spawn((SpatialBundle::default(), soft_body, Name::new("Soft Body"))).with_children(|parent|{
+    // one material for all the nodes, big bodies would otherwise make thousands
+    let material = materials.add(color);
+
+    let node_vec = soft_body.nodes.clone();
+    let connection_vec = soft_body.connections.clone();
+
+    return commands.spawn((SpatialBundle::default(), soft_body, Name::new("Soft Body"))).with_children(|parent|{
         // I guess Im a noob for not using enumerate
         let mut counter: usize = 0;
 
@@ -667,7 +1359,7 @@ fn spawn_sb(
             parent.spawn((
                 MaterialMesh2dBundle{
                     mesh: shape.clone(),
-                    material: materials.add(color),
+                    material: material.clone(),
                     transform: Transform{
                         translation: node.read_pos.extend(0.0),
                         ..default()
@@ -709,21 +1401,35 @@ fn spawn_sb(
                 ConnectionIndex{i1:connection.i1, i2:connection.i2}
             ));
         }
-    });
-    
-
-    info!("Spawned new Soft Body");
+    }).id();
 }
 
 fn update_processes(
-    mut SB_query: Query<&mut SB>,
-    time: Res<Time>,
+    mut SB_query: Query<(Entity, &mut SB)>,
+    mut rigid_query: Query<(Entity, &mut RigidBody)>,
+    mut fluid_query: Query<&mut Fluid>,
+    water_query: Query<&WaterRegion>,
+    settings: Res<SBSettings>,
+    mut contacts: ResMut<SBContacts>,
+    mut step: ResMut<SimStep>,
 ){
+    if settings.paused{
+        return;
+    }
+
     for i in 0..ITERATION_COUNT{
-        update_sb(&mut SB_query, 0.1 as f32);
-        update_sb_collisions(&mut SB_query, 0.1 as f32);
+        update_water(&mut SB_query, &water_query, 0.1 as f32, settings.gravity);
+        update_fields(&mut SB_query, 0.1 as f32, settings.body_gravity);
+        update_sb(&mut SB_query, 0.1 as f32, settings.gravity);
+        update_rigid_bodies(&mut rigid_query, 0.1 as f32, settings.gravity);
+        update_fluid(&mut fluid_query, 0.1 as f32, settings.gravity, i);
+        update_sb_collisions(&mut SB_query, &mut contacts);
+        update_rigid_collisions(&mut SB_query, &mut rigid_query);
+        update_fluid_collisions(&mut SB_query, &mut fluid_query);
     }
 
+    step.0 += 1;
+
     // println!("new");
 
     // let mut counter = 0;
@@ -746,16 +1452,17 @@ fn update_sb_read_pos(
 }
 
 fn update_sb(
-    mut sbObjectQuery: &mut Query<&mut SB>,
+    sbObjectQuery: &mut Query<(Entity, &mut SB)>,
     dt: f32,
+    gravity: Vec2,
 ){
-    for mut sbObject in sbObjectQuery{
+    for (_, mut sbObject) in sbObjectQuery.iter_mut(){
         simulation_update(&mut sbObject, dt as f32);
 
         skeleton_simulation(&mut sbObject, dt as f32);
 
         for mut node in &mut sbObject.nodes{
-            node.vel -= GRAVITY * dt * ITERATION_DELTA;
+            node.vel -= gravity * dt * ITERATION_DELTA;
             node.write_pos += node.vel * dt * ITERATION_DELTA;
         }
         update_sb_read_pos(&mut sbObject);
@@ -768,45 +1475,142 @@ fn update_sb(
         sbObject.angle = sbObject.get_angle();
 
         sbObject.update_skeleton();
+
+        plastic_flow(&mut sbObject, dt);
+
+        inflation_flow(&mut sbObject, dt);
     }
 }
 
-fn update_sb_collisions(
-    mut sbObjectQuery: &mut Query<&mut SB>,
+// moves the size of the rest shape toward target_scale by at most INFLATE_RATE, about
+// the center. The springs push the nodes after it, so the body grows into whatever is around
+fn inflation_flow(
+    sb: &mut SB,
+    dt: f32,
+){
+    if sb.scale == sb.target_scale{
+        return;
+    }
+
+    let step = 1.0 + INFLATE_RATE * dt * ITERATION_DELTA;
+
+    let scale = if sb.target_scale > sb.scale{
+        (sb.scale * step).min(sb.target_scale)
+    }
+    else{
+        (sb.scale / step).max(sb.target_scale)
+    };
+
+    let ratio = scale / sb.scale;
+
+    for connection in &mut sb.connections{
+        connection.resting_length *= ratio;
+    }
+
+    // only the lengths change, base_skeleton_norm stays as it is
+    for vec in &mut sb.base_skeleton{
+        *vec *= ratio;
+    }
+
+    sb.scale = scale;
+}
+
+// whatever is bent past PLASTIC_YIELD slowly becomes the new rest shape,
+// the springs and the skeleton both, so clay stays dented
+fn plastic_flow(
+    sb: &mut SB,
     dt: f32,
 ){
-    let mut iter = sbObjectQuery.iter_combinations_mut();
+    if sb.material.plasticity <= 0.0{
+        return;
+    }
+
+    let rate = (sb.material.plasticity * dt * ITERATION_DELTA).min(1.0);
+
+    for index in 0..sb.connections.len(){
+        let connection = &sb.connections[index];
+        let length = sb.nodes[connection.i1].read_pos.distance(sb.nodes[connection.i2].read_pos);
 
-    while let Some([mut SB1, mut SB2]) =
-        iter.fetch_next()
-    {
-        soft_body_collision(&mut SB1, &mut SB2);
-        soft_body_collision(&mut SB2, &mut SB1);
+        let give = connection.resting_length * PLASTIC_YIELD;
+        let excess = length - length.clamp(connection.resting_length - give, connection.resting_length + give);
+
+        sb.connections[index].resting_length += excess * rate;
+    }
+
+    for index in 0..sb.node_num{
+        // where the node is in the body's own frame, like base_skeleton
+        let local = vec_rotate(&(sb.nodes[index].read_pos - sb.center), -sb.angle);
+        let offset = local - sb.base_skeleton[index];
+
+        let give = sb.base_skeleton[index].length() * PLASTIC_YIELD;
+        let excess = offset.length() - give;
+
+        if excess <= 0.0{
+            continue;
+        }
+
+        sb.base_skeleton[index] += offset.normalize() * excess * rate;
+        sb.base_skeleton_norm[index] = sb.base_skeleton[index].normalize_or_zero();
     }
-    
+}
+
+// finds every contact between bodies for this substep, then solves them all together
+fn update_sb_collisions(
+    sbObjectQuery: &mut Query<(Entity, &mut SB)>,
+    contacts: &mut SBContacts,
+){
+    contacts.contacts.clear();
+
+    for [(entity1, SB1), (entity2, SB2)] in sbObjectQuery.iter_combinations(){
+        soft_body_collision(SB1, SB2, [entity1, entity2], &mut contacts.contacts);
+        soft_body_collision(SB2, SB1, [entity2, entity1], &mut contacts.contacts);
+        edge_collision(SB1, SB2, [entity1, entity2], &mut contacts.contacts);
+        capsule_collision(SB1, SB2, [entity1, entity2], &mut contacts.contacts);
+        capsule_collision(SB2, SB1, [entity2, entity1], &mut contacts.contacts);
+    }
+
+    solve_contacts(sbObjectQuery, contacts);
 }
 
 fn container_collision(
     mut sbObject: &mut SB,
 ){
+    let material = sbObject.material.clone();
+
     for mut node in &mut sbObject.nodes{
         if node.read_pos.y < -HALF_DIM.y{
             node.write_pos.y = -HALF_DIM.y;
-            node.vel.y = 0.0;
+            node.vel = wall_response(node.vel, Vec2::Y, &material);
         }
 
         if node.read_pos.x > HALF_DIM.x{
             node.write_pos.x = HALF_DIM.x;
-            node.vel.x = 0.0;
+            node.vel = wall_response(node.vel, -Vec2::X, &material);
         }
 
         else if node.read_pos.x < -HALF_DIM.x{
             node.write_pos.x = -HALF_DIM.x;
-            node.vel.x = 0.0;
+            node.vel = wall_response(node.vel, Vec2::X, &material);
         }
     }
 }
 
+// the speed a node leaves a wall with. It bounces back by the restitution and
+// the friction takes off sideways speed, at most as much as the bounce gave it
+fn wall_response(
+    vel: Vec2,
+    normal: Vec2,
+    material: &SBMaterial,
+) -> Vec2{
+    let normal_vel = vel.dot(normal);
+    let hit = (-normal_vel).max(0.0);
+
+    let sideways = vel - normal * normal_vel;
+    let grip = material.friction * hit * (1.0 + material.restitution);
+
+    return sideways - sideways.clamp_length_max(grip) + normal * hit * material.restitution;
+}
+
 fn simulation_update(
     mut sbObject: &mut SB,
     dt: f32,
@@ -830,14 +1634,17 @@ fn simulation_update(
 
         let spring_strength = connection.resting_length - vec_length;
 
-        let force = ((DEFAULT_STIFFNESS * spring_strength) - (dot * 0.5 * DEFAULT_DAMPENING)).clamp(-1000.0, 1000.0);
+        let force = ((connection.stiffness * spring_strength) - (dot * 0.5 * connection.dampening)).clamp(-1000.0, 1000.0);
 
         let vector_force = vec_norm * force * dt * ITERATION_DELTA;
 
         // println!("f:{} f1:{} f2:{} p1:{:?} p2:{:?} final_f:{:?}", force, DEFAULT_STIFFNESS * spring_strength, dot * 0.5 * DEFAULT_DAMPENING,world_to_screen_coords(node1.read_pos),world_to_screen_coords(node2.read_pos), Vec2::new(vector_force.x, -vector_force.y));
 
-        sbObject.nodes[connection.i1].vel -= vec_norm * force * dt * ITERATION_DELTA;
-        sbObject.nodes[connection.i2].vel += vec_norm * force * dt * ITERATION_DELTA;
+        let inverse_mass1 = sbObject.nodes[connection.i1].inverse_mass();
+        let inverse_mass2 = sbObject.nodes[connection.i2].inverse_mass();
+
+        sbObject.nodes[connection.i1].vel -= vec_norm * force * inverse_mass1 * dt * ITERATION_DELTA;
+        sbObject.nodes[connection.i2].vel += vec_norm * force * inverse_mass2 * dt * ITERATION_DELTA;
 
         // println!("new vec1 {:?} new vec2 {:?}", Vec2::new(sbObject.nodes[connection.i1].vel.x, -sbObject.nodes[connection.i1].vel.y), Vec2::new(sbObject.nodes[connection.i2].vel.x, -sbObject.nodes[connection.i2].vel.y));
     }
@@ -847,7 +1654,8 @@ fn skeleton_simulation(
     mut sbObject: &mut SB,
     dt: f32,
 ){
-    for index in 0..(sbObject.node_num as usize){
+    for index in 0..sbObject.node_num{
+        let skeleton_stiffness = sbObject.material.skeleton_stiffness;
         let mut node1 = &mut sbObject.nodes[index];
         let skeleton_pos = &sbObject.skeleton[index];
 
@@ -860,12 +1668,12 @@ fn skeleton_simulation(
 
         // println!("skel_pos:{:?} node_pos:{:?} vec:{:?}", world_to_screen_coords(*skeleton_pos), world_to_screen_coords(node1.read_pos), Vec2::new(vec.x, -vec.y));
 
-        let force = (SKELETON_STIFFNESS * -vec.length()).clamp(-1000.0, 1000.0);
+        let force = (skeleton_stiffness * -vec.length()).clamp(-1000.0, 1000.0);
         // let force = 1.0;
 
         // println!("pos {}", vec_norm);
 
-        node1.vel -= vec_norm * force * dt * ITERATION_DELTA;
+        node1.vel -= vec_norm * force * node1.inverse_mass() * dt * ITERATION_DELTA;
     }
 }
 
@@ -901,4 +1709,99 @@ fn update_sb_draw(
             }
         }
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    // a closed triangle, clockwise
+    fn triangle() -> (Vec<SBNode>, Vec<SBConnection>){
+        let nodes = vec![SBNode::new(Vec2::new(0.0, 0.0)), SBNode::new(Vec2::new(0.0, 10.0)), SBNode::new(Vec2::new(10.0, 0.0))];
+        let connections = vec![SBConnection::new(0, 1, true, 10.0), SBConnection::new(1, 2, true, 14.0), SBConnection::new(2, 0, true, 10.0)];
+
+        return (nodes, connections);
+    }
+
+    #[test]
+    fn triangle_is_valid(){
+        let (nodes, connections) = triangle();
+
+        assert_eq!(SB::validate(&nodes, &connections, false), Ok(()));
+        assert!(SB::try_new(&nodes, &connections, false).is_ok());
+    }
+
+    #[test]
+    fn too_few_nodes(){
+        let (mut nodes, _) = triangle();
+        nodes.pop();
+
+        assert_eq!(SB::validate(&nodes, &[SBConnection::new(0, 1, true, 10.0)], true), Err(SBError::TooFewNodes{count: 2}));
+    }
+
+    #[test]
+    fn index_out_of_range(){
+        let (nodes, mut connections) = triangle();
+        connections[1].i2 = 3;
+
+        assert_eq!(SB::validate(&nodes, &connections, false), Err(SBError::IndexOutOfRange{connection: 1, index: 3}));
+    }
+
+    #[test]
+    fn self_connection(){
+        let (nodes, mut connections) = triangle();
+        connections.push(SBConnection::new(2, 2, false, 10.0));
+
+        assert_eq!(SB::validate(&nodes, &connections, false), Err(SBError::SelfConnection{connection: 3}));
+    }
+
+    #[test]
+    fn duplicate_connection(){
+        let (nodes, mut connections) = triangle();
+        // the other way around is the same spring
+        connections.push(SBConnection::new(1, 0, false, 10.0));
+
+        assert_eq!(SB::validate(&nodes, &connections, false), Err(SBError::DuplicateConnection{first: 0, second: 3}));
+    }
+
+    #[test]
+    fn zero_length_spring(){
+        let (nodes, mut connections) = triangle();
+        connections[2].resting_length = 0.0;
+
+        assert_eq!(SB::validate(&nodes, &connections, false), Err(SBError::ZeroLengthSpring{connection: 2}));
+
+        connections[2].resting_length = f32::NAN;
+
+        assert_eq!(SB::validate(&nodes, &connections, false), Err(SBError::ZeroLengthSpring{connection: 2}));
+    }
+
+    #[test]
+    fn invalid_mass(){
+        for mass in [0.0, -1.0, f32::NAN, f32::INFINITY]{
+            let (mut nodes, connections) = triangle();
+            nodes[1].mass = mass;
+
+            assert_eq!(SB::validate(&nodes, &connections, false), Err(SBError::InvalidMass{node: 1}));
+        }
+    }
+
+    #[test]
+    fn open_edge_loop(){
+        let (nodes, mut connections) = triangle();
+        connections[2].is_edge = false;
+
+        assert_eq!(SB::validate(&nodes, &connections, false), Err(SBError::OpenEdgeLoop{node: 0}));
+        // fine for a chain
+        assert_eq!(SB::validate(&nodes, &connections, true), Ok(()));
+    }
+
+    #[test]
+    fn non_manifold_edge(){
+        let (mut nodes, mut connections) = triangle();
+        nodes.push(SBNode::new(Vec2::new(-10.0, 0.0)));
+        connections.push(SBConnection::new(0, 3, true, 10.0));
+
+        assert_eq!(SB::validate(&nodes, &connections, true), Err(SBError::NonManifoldEdge{node: 0}));
+    }
+}
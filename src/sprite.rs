@@ -0,0 +1,385 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+        render_resource::TextureFormat,
+        texture::{CompressedImageFormats, ImageSampler, ImageType, TextureError},
+    },
+    prelude::*,
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+    utils::{HashMap, HashSet},
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+use crate::settings::*;
+use crate::soft_body::*;
+use crate::definition::*;
+use crate::meshing::*;
+
+// A *.sb.png turns into a soft body shaped like the opaque part of the image:
+// marching squares over the alpha channel gives the outline, which gets
+// simplified, resampled and meshed. Every node remembers where it sits on the
+// image so the picture can be stretched over the body as it deforms.
+
+pub struct SBSpritePlugin;
+
+impl Plugin for SBSpritePlugin{
+    fn build(&self, app: &mut App){
+        app.init_asset_loader::<SBSpriteLoader>()
+        .add_systems(Update, (add_texture_meshes, update_texture_meshes).chain().after(SBSet::Simulate));
+    }
+}
+
+// the image and where every node is on it. Goes on the body entity
+#[derive(Component, Clone, Debug, Serialize, Deserialize)]
+pub struct SBTexture{
+    pub path: String,
+    pub uvs: Vec<Vec2>,
+    pub triangles: Vec<[usize; 3]>,
+}
+
+// the mesh the texture is drawn with, rebuilt from the node positions every frame
+#[derive(Component)]
+pub struct SBTextureMesh(pub Handle<Mesh>);
+
+// can be changed per file with a .meta file
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SBSpriteLoaderSettings{
+    // world units per pixel
+    pub scale: f32,
+    // pixels with less alpha than this are outside the body
+    pub alpha_threshold: f32,
+    // how far the simplified outline can be off the traced one, in pixels
+    pub tolerance: f32,
+    pub spacing: f32,
+}
+
+impl Default for SBSpriteLoaderSettings{
+    fn default() -> Self{
+        Self{scale: 1.0, alpha_threshold: 0.5, tolerance: 1.0, spacing: DEFAULT_RESTING_LENGTH * 0.5}
+    }
+}
+
+impl SBSpriteLoaderSettings{
+    // a spacing of 0 never finishes meshing, a scale of 0 squashes the outline into a point
+    pub fn validate(&self) -> Result<(), SBSpriteLoaderError>{
+        if !(self.spacing > 0.0 && self.spacing.is_finite()){
+            return Err(SBSpriteLoaderError::Settings(format!("spacing {} has to be above 0", self.spacing)));
+        }
+
+        if !(self.scale > 0.0 && self.scale.is_finite()){
+            return Err(SBSpriteLoaderError::Settings(format!("scale {} has to be above 0", self.scale)));
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(Debug)]
+pub enum SBSpriteLoaderError{
+    Io(std::io::Error),
+    Image(TextureError),
+    NoOutline,
+    Invalid(SBError),
+    Settings(String),
+}
+
+impl fmt::Display for SBSpriteLoaderError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        match self{
+            SBSpriteLoaderError::Io(err) => write!(f, "could not read sprite: {}", err),
+            SBSpriteLoaderError::Image(err) => write!(f, "could not decode sprite: {}", err),
+            SBSpriteLoaderError::NoOutline => write!(f, "sprite has no opaque area big enough for a soft body"),
+            SBSpriteLoaderError::Invalid(err) => write!(f, "sprite makes an invalid soft body: {}", err),
+            SBSpriteLoaderError::Settings(err) => write!(f, "bad sprite loader settings: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SBSpriteLoaderError{}
+
+impl From<std::io::Error> for SBSpriteLoaderError{
+    fn from(err: std::io::Error) -> Self{
+        SBSpriteLoaderError::Io(err)
+    }
+}
+
+impl From<TextureError> for SBSpriteLoaderError{
+    fn from(err: TextureError) -> Self{
+        SBSpriteLoaderError::Image(err)
+    }
+}
+
+impl From<SBError> for SBSpriteLoaderError{
+    fn from(err: SBError) -> Self{
+        SBSpriteLoaderError::Invalid(err)
+    }
+}
+
+#[derive(Default)]
+pub struct SBSpriteLoader;
+
+impl AssetLoader for SBSpriteLoader{
+    type Asset = SBDefinition;
+    type Settings = SBSpriteLoaderSettings;
+    type Error = SBSpriteLoaderError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        settings: &'a SBSpriteLoaderSettings,
+        load_context: &'a mut LoadContext<'_>,
+    ) -> Result<SBDefinition, SBSpriteLoaderError>{
+        settings.validate()?;
+
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+
+        let image = Image::from_buffer(
+            &bytes,
+            ImageType::Extension("png"),
+            CompressedImageFormats::NONE,
+            true,
+            ImageSampler::Default,
+            RenderAssetUsages::default(),
+        )?;
+
+        let mut definition = sprite_to_definition(&image, settings).ok_or(SBSpriteLoaderError::NoOutline)?;
+        definition.validate()?;
+
+        // the image itself is a sub asset, the texture points at it by path so
+        // spawn inputs stay serializable
+        load_context.add_labeled_asset("texture".to_string(), image);
+
+        if let Some(texture) = &mut definition.texture{
+            texture.path = format!("{}#texture", load_context.path().to_string_lossy());
+        }
+
+        return Ok(definition);
+    }
+
+    fn extensions(&self) -> &[&str]{
+        return &["sb.png"];
+    }
+}
+
+// the body for the biggest opaque blob in the image, centered on the image
+pub fn sprite_to_definition(
+    image: &Image,
+    settings: &SBSpriteLoaderSettings,
+) -> Option<SBDefinition>{
+    let width = image.width() as usize;
+    let height = image.height() as usize;
+
+    let rgba = if image.texture_descriptor.format == TextureFormat::Rgba8UnormSrgb{
+        image.clone()
+    }
+    else{
+        image.convert(TextureFormat::Rgba8UnormSrgb)?
+    };
+
+    let alpha: Vec<f32> = rgba.data.chunks_exact(4).map(|pixel| pixel[3] as f32 / 255.0).collect();
+
+    if alpha.len() != width * height{
+        return None;
+    }
+
+    let contours = marching_squares(&alpha, width, height, settings.alpha_threshold);
+
+    // holes and specks are left out, the biggest loop is the body
+    let contour = contours.into_iter().max_by(|a, b| signed_area(a).abs().total_cmp(&signed_area(b).abs()))?;
+
+    let pixel_center = Vec2::new(width as f32 - 1.0, height as f32 - 1.0) / 2.0;
+
+    // pixel coordinates go down, the world goes up
+    let to_world = |pt: Vec2| Vec2::new(pt.x - pixel_center.x, pixel_center.y - pt.y) * settings.scale;
+    let to_uv = |pos: Vec2| Vec2::new(
+        (pos.x / settings.scale + pixel_center.x + 0.5) / width as f32,
+        (pixel_center.y - pos.y / settings.scale + 0.5) / height as f32,
+    );
+
+    let simplified = simplify_outline(&contour, settings.tolerance);
+    let outline: Vec<Vec2> = simplified.iter().map(|pt| to_world(*pt)).collect();
+
+    let resampled = clean_outline(&resample_outline(&outline, settings.spacing));
+
+    if resampled.len() < 3 || signed_area(&resampled).abs() < settings.spacing * settings.spacing * 0.5{
+        return None;
+    }
+
+    let (points, triangles) = triangulate_outline(&resampled, Some(settings.spacing));
+    let (nodes, connections) = mesh_triangulation(&points, &triangles, resampled.len());
+
+    let mut definition = SBDefinition::from_mesh(&nodes, &connections, SBMaterial::default());
+
+    definition.texture = Some(SBTexture{
+        path: String::new(),
+        uvs: points.iter().map(|pos| to_uv(*pos)).collect(),
+        triangles,
+    });
+
+    return Some(definition);
+}
+
+// closed loops in pixel coordinates (pixel centers on whole numbers) around everything
+// with at least `threshold` alpha. Anything past the border counts as transparent so
+// every loop closes
+fn marching_squares(
+    alpha: &[f32],
+    width: usize,
+    height: usize,
+    threshold: f32,
+) -> Vec<Vec<Vec2>>{
+    let value = |x: i32, y: i32| -> f32{
+        if x < 0 || y < 0 || x >= width as i32 || y >= height as i32{
+            return 0.0;
+        }
+
+        return alpha[y as usize * width + x as usize];
+    };
+
+    let inside = |x: i32, y: i32| value(x, y) >= threshold;
+
+    // where the threshold crosses the edge from (x, y) to the next sample right (or down)
+    let crossing = |x: i32, y: i32, vertical: bool| -> Vec2{
+        let (x2, y2) = if vertical {(x, y + 1)} else {(x + 1, y)};
+
+        let v1 = value(x, y);
+        let v2 = value(x2, y2);
+
+        let t = if (v2 - v1).abs() > f32::EPSILON {((threshold - v1) / (v2 - v1)).clamp(0.0, 1.0)} else {0.5};
+
+        return Vec2::new(x as f32, y as f32).lerp(Vec2::new(x2 as f32, y2 as f32), t);
+    };
+
+    // every crossing point is shared by the two cells next to its edge,
+    // each of which links it to one other point
+    let mut links: HashMap<(i32, i32, bool), Vec<(i32, i32, bool)>> = HashMap::new();
+
+    for y in -1..(height as i32){
+        for x in -1..(width as i32){
+            let corners = [inside(x, y), inside(x + 1, y), inside(x + 1, y + 1), inside(x, y + 1)];
+
+            // top, right, bottom, left
+            let edges = [(x, y, false), (x + 1, y, true), (x, y + 1, false), (x, y, true)];
+
+            let crossed: Vec<usize> = (0..4).filter(|&k| corners[k] != corners[(k + 1) % 4]).collect();
+
+            let pairs = match crossed.len(){
+                2 => vec![(crossed[0], crossed[1])],
+                4 => {
+                    // saddle, the average in the middle decides which corners are joined
+                    let center = (value(x, y) + value(x + 1, y) + value(x + 1, y + 1) + value(x, y + 1)) / 4.0;
+
+                    if (center >= threshold) == corners[0]{
+                        vec![(0, 1), (2, 3)]
+                    }
+                    else{
+                        vec![(3, 0), (1, 2)]
+                    }
+                }
+                _ => continue,
+            };
+
+            for (a, b) in pairs{
+                links.entry(edges[a]).or_default().push(edges[b]);
+                links.entry(edges[b]).or_default().push(edges[a]);
+            }
+        }
+    }
+
+    let mut contours = Vec::new();
+    let mut visited: HashSet<(i32, i32, bool)> = HashSet::new();
+
+    let mut starts: Vec<(i32, i32, bool)> = links.keys().copied().collect();
+    // hash map order isnt stable, the meshes should be
+    starts.sort();
+
+    for start in starts{
+        if visited.contains(&start){
+            continue;
+        }
+
+        let mut contour = Vec::new();
+        let mut previous = start;
+        let mut current = start;
+
+        loop{
+            visited.insert(current);
+            contour.push(crossing(current.0, current.1, current.2));
+
+            let next = links[&current].iter().copied().find(|&edge| edge != previous && !visited.contains(&edge));
+
+            let Some(next) = next else{
+                break;
+            };
+
+            previous = current;
+            current = next;
+        }
+
+        if contour.len() >= 3{
+            contours.push(contour);
+        }
+    }
+
+    return contours;
+}
+
+fn texture_mesh(
+    sb: &SB,
+    texture: &SBTexture,
+) -> Mesh{
+    let positions: Vec<[f32; 3]> = sb.nodes.iter().map(|node| [node.read_pos.x, node.read_pos.y, 0.0]).collect();
+    let uvs: Vec<[f32; 2]> = texture.uvs.iter().map(|uv| [uv.x, uv.y]).collect();
+    let indices: Vec<u32> = texture.triangles.iter().flat_map(|triangle| triangle.iter().map(|&i| i as u32)).collect();
+
+    return Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_attribute(Mesh::ATTRIBUTE_UV_0, uvs)
+    .with_inserted_indices(Indices::U32(indices));
+}
+
+fn add_texture_meshes(
+    mut commands: Commands,
+    sb_query: Query<(Entity, &SB, &SBTexture), Without<SBTextureMesh>>,
+    asset_server: Res<AssetServer>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+){
+    for (entity, sb, texture) in &sb_query{
+        let mesh = meshes.add(texture_mesh(sb, texture));
+
+        let material = materials.add(ColorMaterial{
+            texture: Some(asset_server.load(texture.path.clone())),
+            ..default()
+        });
+
+        let child = commands.spawn(MaterialMesh2dBundle{
+            mesh: Mesh2dHandle(mesh.clone()),
+            material,
+            // behind the nodes and connections
+            transform: Transform::from_xyz(0.0, 0.0, -1.0),
+            ..default()
+        }).id();
+
+        commands.entity(entity).add_child(child).insert(SBTextureMesh(mesh));
+    }
+}
+
+fn update_texture_meshes(
+    sb_query: Query<(&SB, &SBTextureMesh)>,
+    mut meshes: ResMut<Assets<Mesh>>,
+){
+    for (sb, texture_mesh) in &sb_query{
+        let Some(mesh) = meshes.get_mut(&texture_mesh.0) else{
+            continue;
+        };
+
+        let positions: Vec<[f32; 3]> = sb.nodes.iter().map(|node| [node.read_pos.x, node.read_pos.y, 0.0]).collect();
+
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+    }
+}
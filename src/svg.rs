@@ -0,0 +1,640 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    math::Affine2,
+    prelude::*,
+};
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+use std::fmt;
+
+use crate::settings::*;
+use crate::definition::*;
+use crate::meshing::*;
+
+// Every closed outline of a `path` or `polygon` in an svg becomes its own soft body.
+// Curves get flattened, the outline resampled to about `spacing` between nodes
+// and then meshed like any other outline. Transforms on the elements and their
+// groups are applied, y gets flipped and the drawing is centered on the origin.
+
+pub struct SBSvgPlugin;
+
+impl Plugin for SBSvgPlugin{
+    fn build(&self, app: &mut App){
+        app.init_asset::<SBDrawing>()
+        .init_asset_loader::<SvgLoader>();
+    }
+}
+
+// all the bodies from one svg file
+#[derive(Asset, TypePath, Clone, Debug)]
+pub struct SBDrawing{
+    pub bodies: Vec<SBDefinition>,
+}
+
+// can be changed per file with a .meta file
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SvgLoaderSettings{
+    pub spacing: f32,
+    pub scale: f32,
+}
+
+impl Default for SvgLoaderSettings{
+    fn default() -> Self{
+        Self{spacing: DEFAULT_RESTING_LENGTH * 0.5, scale: 1.0}
+    }
+}
+
+impl SvgLoaderSettings{
+    // both end up dividing, so anything but a positive number breaks the flattening or the resampling
+    pub fn validate(&self) -> Result<(), SvgError>{
+        if !(self.spacing > 0.0 && self.spacing.is_finite()){
+            return Err(SvgError::Settings(format!("spacing {} has to be above 0", self.spacing)));
+        }
+
+        if !(self.scale > 0.0 && self.scale.is_finite()){
+            return Err(SvgError::Settings(format!("scale {} has to be above 0", self.scale)));
+        }
+
+        return Ok(());
+    }
+}
+
+#[derive(Debug)]
+pub enum SvgError{
+    Io(std::io::Error),
+    Xml(roxmltree::Error),
+    Path(String),
+    Settings(String),
+}
+
+impl fmt::Display for SvgError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result{
+        match self{
+            SvgError::Io(err) => write!(f, "could not read svg: {}", err),
+            SvgError::Xml(err) => write!(f, "could not parse svg: {}", err),
+            SvgError::Path(err) => write!(f, "bad svg path data: {}", err),
+            SvgError::Settings(err) => write!(f, "bad svg loader settings: {}", err),
+        }
+    }
+}
+
+impl std::error::Error for SvgError{}
+
+impl From<std::io::Error> for SvgError{
+    fn from(err: std::io::Error) -> Self{
+        SvgError::Io(err)
+    }
+}
+
+impl From<roxmltree::Error> for SvgError{
+    fn from(err: roxmltree::Error) -> Self{
+        SvgError::Xml(err)
+    }
+}
+
+#[derive(Default)]
+pub struct SvgLoader;
+
+impl AssetLoader for SvgLoader{
+    type Asset = SBDrawing;
+    type Settings = SvgLoaderSettings;
+    type Error = SvgError;
+
+    async fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader<'_>,
+        settings: &'a SvgLoaderSettings,
+        _load_context: &'a mut LoadContext<'_>,
+    ) -> Result<SBDrawing, SvgError>{
+        let mut text = String::new();
+        reader.read_to_string(&mut text).await?;
+
+        return svg_to_drawing(&text, settings);
+    }
+
+    fn extensions(&self) -> &[&str]{
+        return &["svg"];
+    }
+}
+
+pub fn svg_to_drawing(
+    text: &str,
+    settings: &SvgLoaderSettings,
+) -> Result<SBDrawing, SvgError>{
+    settings.validate()?;
+
+    let outlines = svg_outlines(text, settings.spacing * 0.25 / settings.scale)?;
+
+    let mut min = Vec2::splat(f32::MAX);
+    let mut max = Vec2::splat(f32::MIN);
+
+    for outline in &outlines{
+        for pt in outline{
+            min = min.min(*pt);
+            max = max.max(*pt);
+        }
+    }
+
+    let center = (min + max) / 2.0;
+
+    let mut bodies = Vec::new();
+
+    for (index, outline) in outlines.into_iter().enumerate(){
+        // svg has y going down
+        let outline: Vec<Vec2> = outline.iter().map(|pt| {
+            let pt = (*pt - center) * settings.scale;
+            Vec2::new(pt.x, -pt.y)
+        }).collect();
+
+        let resampled = resample_outline(&outline, settings.spacing);
+
+        if resampled.len() < 3 || signed_area(&resampled).abs() < settings.spacing * settings.spacing * 0.5{
+            continue;
+        }
+
+        // one broken outline shouldnt cost the whole drawing
+        let definition = match SBDefinition::from_outline(resampled, Some(settings.spacing)){
+            Ok(definition) => definition,
+            Err(err) => {
+                warn!("Skipped outline {} of svg: {}", index, err);
+                continue;
+            }
+        };
+
+        if let Err(err) = definition.validate(){
+            warn!("Skipped outline {} of svg: {}", index, err);
+            continue;
+        }
+
+        bodies.push(definition);
+    }
+
+    return Ok(SBDrawing{bodies});
+}
+
+// closed outlines in svg coordinates, curves flattened into pieces of about `step`
+pub fn svg_outlines(
+    text: &str,
+    step: f32,
+) -> Result<Vec<Vec<Vec2>>, SvgError>{
+    let document = roxmltree::Document::parse(text)?;
+
+    let mut outlines = Vec::new();
+
+    for node in document.descendants(){
+        if !node.is_element(){
+            continue;
+        }
+
+        let subpaths = match node.tag_name().name(){
+            "path" => parse_path(node.attribute("d").unwrap_or(""), step)?,
+            "polygon" => {
+                let numbers = parse_numbers(node.attribute("points").unwrap_or(""));
+                vec![(numbers.chunks_exact(2).map(|pair| Vec2::new(pair[0], pair[1])).collect(), true)]
+            }
+            _ => continue,
+        };
+
+        let transform = element_transform(node);
+
+        for (points, closed) in subpaths{
+            if !closed || points.len() < 3{
+                continue;
+            }
+
+            outlines.push(points.iter().map(|pt| transform.transform_point2(*pt)).collect());
+        }
+    }
+
+    return Ok(outlines);
+}
+
+// the element's transform with all of its parents' applied on top
+fn element_transform(
+    node: roxmltree::Node,
+) -> Affine2{
+    let mut transform = Affine2::IDENTITY;
+
+    for ancestor in node.ancestors(){
+        if let Some(text) = ancestor.attribute("transform"){
+            transform = parse_transform(text) * transform;
+        }
+    }
+
+    return transform;
+}
+
+fn parse_transform(
+    text: &str,
+) -> Affine2{
+    let mut transform = Affine2::IDENTITY;
+
+    for part in text.split(')'){
+        let Some((name, args)) = part.split_once('(') else{
+            continue;
+        };
+
+        let args = parse_numbers(args);
+        let arg = |i: usize, default: f32| args.get(i).copied().unwrap_or(default);
+
+        let part_transform = match name.trim().trim_start_matches(',').trim(){
+            "matrix" => Affine2::from_cols(Vec2::new(arg(0, 1.0), arg(1, 0.0)), Vec2::new(arg(2, 0.0), arg(3, 1.0)), Vec2::new(arg(4, 0.0), arg(5, 0.0))),
+            "translate" => Affine2::from_translation(Vec2::new(arg(0, 0.0), arg(1, 0.0))),
+            "scale" => Affine2::from_scale(Vec2::new(arg(0, 1.0), arg(1, arg(0, 1.0)))),
+            "rotate" => {
+                let pivot = Vec2::new(arg(1, 0.0), arg(2, 0.0));
+
+                Affine2::from_translation(pivot) * Affine2::from_angle(arg(0, 0.0).to_radians()) * Affine2::from_translation(-pivot)
+            }
+            "skewX" => Affine2::from_cols(Vec2::X, Vec2::new(arg(0, 0.0).to_radians().tan(), 1.0), Vec2::ZERO),
+            "skewY" => Affine2::from_cols(Vec2::new(1.0, arg(0, 0.0).to_radians().tan()), Vec2::Y, Vec2::ZERO),
+            _ => Affine2::IDENTITY,
+        };
+
+        transform *= part_transform;
+    }
+
+    return transform;
+}
+
+// numbers the way svg writes them: "1-2", ".5.5" and "1e-3" are all fine
+fn parse_numbers(
+    text: &str,
+) -> Vec<f32>{
+    let mut tokenizer = Tokenizer{chars: text.chars().collect(), pos: 0};
+    let mut numbers = Vec::new();
+
+    while let Some(number) = tokenizer.number(){
+        numbers.push(number);
+    }
+
+    return numbers;
+}
+
+struct Tokenizer{
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Tokenizer{
+    fn skip_separators(&mut self){
+        while self.pos < self.chars.len() && (self.chars[self.pos].is_whitespace() || self.chars[self.pos] == ','){
+            self.pos += 1;
+        }
+    }
+
+    fn command(&mut self) -> Option<char>{
+        self.skip_separators();
+
+        let c = *self.chars.get(self.pos)?;
+
+        if c.is_ascii_alphabetic() && c != 'e' && c != 'E'{
+            self.pos += 1;
+            return Some(c);
+        }
+
+        return None;
+    }
+
+    fn number(&mut self) -> Option<f32>{
+        self.skip_separators();
+
+        let start = self.pos;
+        let mut seen_dot = false;
+        let mut seen_digit = false;
+
+        if self.pos < self.chars.len() && (self.chars[self.pos] == '-' || self.chars[self.pos] == '+'){
+            self.pos += 1;
+        }
+
+        while self.pos < self.chars.len(){
+            let c = self.chars[self.pos];
+
+            if c.is_ascii_digit(){
+                seen_digit = true;
+            }
+            else if c == '.' && !seen_dot{
+                seen_dot = true;
+            }
+            else if (c == 'e' || c == 'E') && seen_digit{
+                self.pos += 1;
+
+                if self.pos < self.chars.len() && (self.chars[self.pos] == '-' || self.chars[self.pos] == '+'){
+                    self.pos += 1;
+                }
+
+                continue;
+            }
+            else{
+                break;
+            }
+
+            self.pos += 1;
+        }
+
+        if !seen_digit{
+            self.pos = start;
+            return None;
+        }
+
+        let text: String = self.chars[start..self.pos].iter().collect();
+
+        return text.parse().ok();
+    }
+
+    // arc flags can be written without anything between them, like "a1 1 0 011 1"
+    fn flag(&mut self) -> Option<bool>{
+        self.skip_separators();
+
+        let c = *self.chars.get(self.pos)?;
+
+        if c == '0' || c == '1'{
+            self.pos += 1;
+            return Some(c == '1');
+        }
+
+        return None;
+    }
+
+    fn at_end(&mut self) -> bool{
+        self.skip_separators();
+        return self.pos >= self.chars.len();
+    }
+}
+
+fn flatten_cubic(
+    points: &mut Vec<Vec2>,
+    p0: Vec2,
+    p1: Vec2,
+    p2: Vec2,
+    p3: Vec2,
+    step: f32,
+){
+    let length = p0.distance(p1) + p1.distance(p2) + p2.distance(p3);
+    let count = ((length / step).ceil() as usize).clamp(1, 256);
+
+    for i in 1..=count{
+        let t = (i as f32) / (count as f32);
+        let u = 1.0 - t;
+
+        points.push(p0 * u * u * u + p1 * 3.0 * u * u * t + p2 * 3.0 * u * t * t + p3 * t * t * t);
+    }
+}
+
+fn flatten_quadratic(
+    points: &mut Vec<Vec2>,
+    p0: Vec2,
+    p1: Vec2,
+    p2: Vec2,
+    step: f32,
+){
+    let length = p0.distance(p1) + p1.distance(p2);
+    let count = ((length / step).ceil() as usize).clamp(1, 256);
+
+    for i in 1..=count{
+        let t = (i as f32) / (count as f32);
+        let u = 1.0 - t;
+
+        points.push(p0 * u * u + p1 * 2.0 * u * t + p2 * t * t);
+    }
+}
+
+// endpoint to center conversion from the svg spec
+fn flatten_arc(
+    points: &mut Vec<Vec2>,
+    from: Vec2,
+    radii: Vec2,
+    rotation: f32,
+    (large_arc, sweep): (bool, bool),
+    to: Vec2,
+    step: f32,
+){
+    let mut rx = radii.x.abs();
+    let mut ry = radii.y.abs();
+
+    if rx == 0.0 || ry == 0.0 || from == to{
+        points.push(to);
+        return;
+    }
+
+    let (sin_phi, cos_phi) = rotation.to_radians().sin_cos();
+
+    let half = (from - to) / 2.0;
+    let x1 = cos_phi * half.x + sin_phi * half.y;
+    let y1 = -sin_phi * half.x + cos_phi * half.y;
+
+    let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+
+    if lambda > 1.0{
+        rx *= lambda.sqrt();
+        ry *= lambda.sqrt();
+    }
+
+    let numerator = (rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1).max(0.0);
+    let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+
+    let mut coef = (numerator / denominator).sqrt();
+
+    if large_arc == sweep{
+        coef = -coef;
+    }
+
+    let cx1 = coef * rx * y1 / ry;
+    let cy1 = -coef * ry * x1 / rx;
+
+    let mid = (from + to) / 2.0;
+    let center = Vec2::new(cos_phi * cx1 - sin_phi * cy1 + mid.x, sin_phi * cx1 + cos_phi * cy1 + mid.y);
+
+    let start = Vec2::new((x1 - cx1) / rx, (y1 - cy1) / ry);
+    let end = Vec2::new((-x1 - cx1) / rx, (-y1 - cy1) / ry);
+
+    let theta = start.y.atan2(start.x);
+    let mut delta = end.y.atan2(end.x) - theta;
+
+    if sweep && delta < 0.0{
+        delta += 2.0 * PI;
+    }
+    else if !sweep && delta > 0.0{
+        delta -= 2.0 * PI;
+    }
+
+    let count = ((delta.abs() * rx.max(ry) / step).ceil() as usize).clamp(1, 256);
+
+    for i in 1..=count{
+        let angle = theta + delta * (i as f32) / (count as f32);
+        let (sin, cos) = angle.sin_cos();
+
+        points.push(Vec2::new(
+            center.x + rx * cos_phi * cos - ry * sin_phi * sin,
+            center.y + rx * sin_phi * cos + ry * cos_phi * sin,
+        ));
+    }
+}
+
+// flattened subpaths of a path's d attribute, with whether they are closed
+pub fn parse_path(
+    d: &str,
+    step: f32,
+) -> Result<Vec<(Vec<Vec2>, bool)>, SvgError>{
+    let mut tokenizer = Tokenizer{chars: d.chars().collect(), pos: 0};
+
+    let mut subpaths = Vec::new();
+    let mut points: Vec<Vec2> = Vec::new();
+
+    let mut current = Vec2::ZERO;
+    let mut start = Vec2::ZERO;
+    // second control point of the last curve, for S and T
+    let mut last_control: Option<(char, Vec2)> = None;
+
+    let mut command = ' ';
+
+    let finish = |points: &mut Vec<Vec2>, subpaths: &mut Vec<(Vec<Vec2>, bool)>, closed: bool|{
+        if points.len() > 1{
+            let closed = closed || points[0].distance(*points.last().unwrap()) < 1e-3;
+            subpaths.push((points.clone(), closed));
+        }
+        points.clear();
+    };
+
+    let missing = |command: char| SvgError::Path(format!("missing numbers after '{}'", command));
+
+    while !tokenizer.at_end(){
+        if let Some(next) = tokenizer.command(){
+            command = next;
+        }
+        else if command == ' '{
+            return Err(SvgError::Path("path data has to start with a command".to_string()));
+        }
+
+        let relative = command.is_ascii_lowercase();
+        let offset = if relative {current} else {Vec2::ZERO};
+
+        let point = |tokenizer: &mut Tokenizer| -> Result<Vec2, SvgError>{
+            let x = tokenizer.number().ok_or_else(|| missing(command))?;
+            let y = tokenizer.number().ok_or_else(|| missing(command))?;
+            Ok(Vec2::new(x, y) + offset)
+        };
+
+        match command.to_ascii_uppercase(){
+            'M' => {
+                finish(&mut points, &mut subpaths, false);
+
+                current = point(&mut tokenizer)?;
+                start = current;
+                points.push(current);
+
+                // more pairs after a move are lines
+                command = if relative {'l'} else {'L'};
+                last_control = None;
+            }
+            'L' => {
+                current = point(&mut tokenizer)?;
+                points.push(current);
+                last_control = None;
+            }
+            'H' => {
+                let x = tokenizer.number().ok_or_else(|| missing(command))?;
+                current.x = if relative {current.x + x} else {x};
+                points.push(current);
+                last_control = None;
+            }
+            'V' => {
+                let y = tokenizer.number().ok_or_else(|| missing(command))?;
+                current.y = if relative {current.y + y} else {y};
+                points.push(current);
+                last_control = None;
+            }
+            'C' | 'S' => {
+                let control1 = if command.to_ascii_uppercase() == 'C'{
+                    point(&mut tokenizer)?
+                }
+                else{
+                    match last_control{
+                        Some(('C', control)) => current * 2.0 - control,
+                        _ => current,
+                    }
+                };
+
+                let control2 = point(&mut tokenizer)?;
+                let end = point(&mut tokenizer)?;
+
+                flatten_cubic(&mut points, current, control1, control2, end, step);
+
+                current = end;
+                last_control = Some(('C', control2));
+            }
+            'Q' | 'T' => {
+                let control = if command.to_ascii_uppercase() == 'Q'{
+                    point(&mut tokenizer)?
+                }
+                else{
+                    match last_control{
+                        Some(('Q', control)) => current * 2.0 - control,
+                        _ => current,
+                    }
+                };
+
+                let end = point(&mut tokenizer)?;
+
+                flatten_quadratic(&mut points, current, control, end, step);
+
+                current = end;
+                last_control = Some(('Q', control));
+            }
+            'A' => {
+                let rx = tokenizer.number().ok_or_else(|| missing(command))?;
+                let ry = tokenizer.number().ok_or_else(|| missing(command))?;
+                let rotation = tokenizer.number().ok_or_else(|| missing(command))?;
+                let large_arc = tokenizer.flag().ok_or_else(|| missing(command))?;
+                let sweep = tokenizer.flag().ok_or_else(|| missing(command))?;
+                let end = point(&mut tokenizer)?;
+
+                flatten_arc(&mut points, current, Vec2::new(rx, ry), rotation, (large_arc, sweep), end, step);
+
+                current = end;
+                last_control = None;
+            }
+            'Z' => {
+                finish(&mut points, &mut subpaths, true);
+
+                current = start;
+                last_control = None;
+                // a new subpath can start right away without a move
+                points.push(current);
+                command = ' ';
+            }
+            _ => {
+                return Err(SvgError::Path(format!("unknown command '{}'", command)));
+            }
+        }
+    }
+
+    finish(&mut points, &mut subpaths, false);
+
+    return Ok(subpaths);
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    const SQUARE: &str = r#"<svg xmlns="http://www.w3.org/2000/svg"><polygon points="0,0 100,0 100,100 0,100"/></svg>"#;
+
+    #[test]
+    fn polygon_becomes_a_body(){
+        let drawing = svg_to_drawing(SQUARE, &SvgLoaderSettings::default()).unwrap();
+
+        assert_eq!(drawing.bodies.len(), 1);
+    }
+
+    #[test]
+    fn settings_have_to_be_above_zero(){
+        for value in [0.0, -1.0, f32::NAN, f32::INFINITY]{
+            let spacing = SvgLoaderSettings{spacing: value, ..default()};
+            let scale = SvgLoaderSettings{scale: value, ..default()};
+
+            assert!(matches!(svg_to_drawing(SQUARE, &spacing), Err(SvgError::Settings(_))));
+            assert!(matches!(svg_to_drawing(SQUARE, &scale), Err(SvgError::Settings(_))));
+        }
+    }
+}
@@ -0,0 +1,265 @@
+use bevy::{
+    prelude::*,
+    render::{
+        mesh::{Indices, PrimitiveTopology},
+        render_asset::RenderAssetUsages,
+    },
+    sprite::{MaterialMesh2dBundle, Mesh2dHandle},
+};
+use bevy::window::PrimaryWindow;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::*;
+use crate::soft_body::*;
+use crate::meshing::*;
+
+// Still water, cheaper than the fluid and only for soft bodies. Every substep each
+// body gets pushed up by the weight of the water its outlines take up, shared out over
+// the nodes in the water, and the nodes in it get slowed down by the drag. Bodies lighter
+// than the water float, and sit deeper the closer they are to it.
+// H fills the container up to the mouse, J drops a pool around it
+pub struct SBWaterPlugin;
+
+impl Plugin for SBWaterPlugin{
+    fn build(&self, app: &mut App){
+        app.add_systems(Update, add_water.in_set(SBSet::Input));
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub enum WaterShape{
+    // everything below the height, wall to wall
+    Surface{height: f32},
+    // can be concave, it gets cut into triangles for submerged_area
+    Polygon{points: Vec<Vec2>},
+}
+
+#[derive(Component, Clone, Debug, Serialize, Deserialize)]
+pub struct WaterRegion{
+    pub shape: WaterShape,
+    pub density: f32,
+    // how much of a node's speed the water takes per unit of time
+    pub drag: f32,
+    // worked out from the shape when the water is spawned, see prepare
+    #[serde(skip)]
+    outline: Vec<Vec2>,
+    #[serde(skip)]
+    parts: Vec<Vec<Vec2>>,
+}
+
+impl WaterRegion{
+    pub fn surface(height: f32) -> Self{
+        return Self::new(WaterShape::Surface{height});
+    }
+
+    // wound clockwise, like rigid polygons
+    pub fn polygon(points: &[Vec2]) -> Self{
+        let mut points = points.to_vec();
+
+        if signed_area(&points) > 0.0{
+            points.reverse();
+        }
+
+        return Self::new(WaterShape::Polygon{points});
+    }
+
+    fn new(shape: WaterShape) -> Self{
+        let mut region = Self{shape, density: WATER_DENSITY, drag: WATER_DRAG, outline: Vec::new(), parts: Vec::new()};
+        region.prepare();
+
+        return region;
+    }
+
+    // the water never changes shape, so update_water gets the outline and its parts
+    // ready made. Has to be called again after loading it, they arent saved
+    fn prepare(&mut self){
+        self.outline = self.shape_outline();
+        self.parts = self.shape_parts();
+    }
+
+    pub fn outline(&self) -> &[Vec2]{
+        return &self.outline;
+    }
+
+    pub fn convex_parts(&self) -> &[Vec<Vec2>]{
+        return &self.parts;
+    }
+
+    // clockwise. A surface reaches a little past the walls so nodes pushed
+    // against them still count as in the water
+    fn shape_outline(&self) -> Vec<Vec2>{
+        return match &self.shape{
+            WaterShape::Surface{height} => {
+                let (left, right, bottom) = (-HALF_DIM.x - NODE_RADIUS, HALF_DIM.x + NODE_RADIUS, -HALF_DIM.y - NODE_RADIUS);
+
+                vec![Vec2::new(left, bottom), Vec2::new(left, *height), Vec2::new(right, *height), Vec2::new(right, bottom)]
+            }
+            WaterShape::Polygon{points} => points.clone(),
+        };
+    }
+
+    // the outline cut into convex clockwise parts, which is what clip_outline needs
+    fn shape_parts(&self) -> Vec<Vec<Vec2>>{
+        return match &self.shape{
+            WaterShape::Surface{..} => vec![self.shape_outline()],
+            WaterShape::Polygon{points} => {
+                let (points, triangles) = triangulate_outline(points, None);
+
+                // the triangles come counter clockwise
+                triangles.iter().map(|triangle| vec![points[triangle[2]], points[triangle[1]], points[triangle[0]]]).collect()
+            }
+        };
+    }
+}
+
+// the part of `outline` inside the convex clockwise `region`. Cut down one region
+// edge at a time, keeping what is on its right
+fn clip_outline(
+    outline: &[Vec2],
+    region: &[Vec2],
+) -> Vec<Vec2>{
+    let mut clipped = outline.to_vec();
+
+    for i in 0..region.len(){
+        let (a, b) = (region[i], region[(i + 1) % region.len()]);
+        let points = std::mem::take(&mut clipped);
+
+        for j in 0..points.len(){
+            let (p1, p2) = (points[j], points[(j + 1) % points.len()]);
+            let (side1, side2) = (orient(a, b, p1), orient(a, b, p2));
+
+            if side1 <= 0.0{
+                clipped.push(p1);
+            }
+
+            if (side1 <= 0.0) != (side2 <= 0.0){
+                let t = (side1 / (side1 - side2)) as f32;
+                clipped.push(p1 + (p2 - p1) * t);
+            }
+        }
+    }
+
+    return clipped;
+}
+
+// how much of the body is in the region, given as its convex parts, holes taken out.
+// Outer loops are clockwise and so have a negative area, holes a positive one
+pub fn submerged_area(
+    sb: &SB,
+    parts: &[Vec<Vec2>],
+) -> f32{
+    let mut area = 0.0;
+
+    for edge_loop in &sb.boundary{
+        let outline: Vec<Vec2> = edge_loop.iter().map(|&edge| sb.nodes[sb.connections[edge].i1].read_pos).collect();
+
+        for part in parts{
+            area -= signed_area(&clip_outline(&outline, part));
+        }
+    }
+
+    return area.max(0.0);
+}
+
+// open bodies have no outline to take up any water, they only get the drag
+pub fn update_water(
+    sb_query: &mut Query<(Entity, &mut SB)>,
+    water_query: &Query<&WaterRegion>,
+    dt: f32,
+    gravity: Vec2,
+){
+    for region in water_query.iter(){
+        let outline = region.outline();
+
+        let bounding_box = BoundingBox{
+            min_pos: outline.iter().fold(Vec2::MAX, |min, pt| min.min(*pt)),
+            max_pos: outline.iter().fold(Vec2::MIN, |max, pt| max.max(*pt)),
+        };
+
+        let drag = (region.drag * dt * ITERATION_DELTA).min(1.0);
+
+        for (_, mut sb) in sb_query.iter_mut(){
+            if !bounding_box_collision(&sb.bounding_box, &bounding_box){
+                continue;
+            }
+
+            let submerged: Vec<usize> = (0..sb.node_num).filter(|&index| point_in_outline(sb.nodes[index].read_pos, outline)).collect();
+
+            if submerged.is_empty(){
+                continue;
+            }
+
+            let submerged_mass: f32 = submerged.iter().map(|&index| sb.nodes[index].mass).sum();
+
+            // gravity pulls along -gravity, so the water pushes along it. Every node in the
+            // water gets sped up the same, which adds up to the weight of the water pushed aside
+            let lift = gravity * region.density * submerged_area(&sb, region.convex_parts()) / submerged_mass;
+
+            for &index in &submerged{
+                let node = &mut sb.nodes[index];
+
+                node.vel += lift * dt * ITERATION_DELTA;
+                node.vel -= node.vel * drag;
+            }
+        }
+    }
+}
+
+fn water_mesh(
+    outline: &[Vec2],
+) -> Mesh{
+    let (points, triangles) = triangulate_outline(outline, None);
+
+    let positions: Vec<[f32; 3]> = points.iter().map(|pt| [pt.x, pt.y, 0.0]).collect();
+    let indices: Vec<u32> = triangles.iter().flat_map(|triangle| triangle.iter().map(|&i| i as u32)).collect();
+
+    return Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+    .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, positions)
+    .with_inserted_indices(Indices::U32(indices));
+}
+
+// the water doesnt move, so it is drawn once behind everything
+pub fn spawn_water_entity(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<ColorMaterial>,
+    mut region: WaterRegion,
+) -> Entity{
+    // restored and replayed water comes without its outline
+    region.prepare();
+
+    return commands.spawn((
+        MaterialMesh2dBundle{
+            mesh: Mesh2dHandle(meshes.add(water_mesh(region.outline()))),
+            material: materials.add(Color::srgba(0.2, 0.5, 0.9, 0.3)),
+            transform: Transform::from_xyz(0.0, 0.0, -1.0),
+            ..default()
+        },
+        region,
+        Name::new("Water"),
+    )).id();
+}
+
+fn add_water(
+    q_windows: Query<&Window, With<PrimaryWindow>>,
+    input: Res<ButtonInput<KeyCode>>,
+    mut input_writer: EventWriter<SBInput>,
+){
+    let Some(mouse_position) = q_windows.single().cursor_position() else{
+        return;
+    };
+
+    let mut pos = mouse_position - HALF_DIM;
+    pos.y = -pos.y;
+
+    if input.just_pressed(KeyCode::KeyH){
+        input_writer.send(SBInput::SpawnWater{region: WaterRegion::surface(pos.y)});
+    }
+
+    if input.just_pressed(KeyCode::KeyJ){
+        let half = WATER_POOL_SIZE / 2.0;
+        let corners = [pos - half, pos + Vec2::new(-half.x, half.y), pos + half, pos + Vec2::new(half.x, -half.y)];
+
+        input_writer.send(SBInput::SpawnWater{region: WaterRegion::polygon(&corners)});
+    }
+}